@@ -0,0 +1,46 @@
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+use k8s_openapi::chrono::{DateTime, Utc};
+
+/// Formats the time elapsed between `time` and `now` in its largest whole unit, similarly to the
+/// `AGE` column printed by `kubectl`, e.g. `12d` or `5h`.
+pub fn format_age(time: &Time, now: DateTime<Utc>) -> String {
+    let elapsed = now.signed_duration_since(time.0).max(Default::default());
+
+    if elapsed.num_days() > 0 {
+        format!("{}d", elapsed.num_days())
+    } else if elapsed.num_hours() > 0 {
+        format!("{}h", elapsed.num_hours())
+    } else if elapsed.num_minutes() > 0 {
+        format!("{}m", elapsed.num_minutes())
+    } else {
+        format!("{}s", elapsed.num_seconds())
+    }
+}
+
+/// Time of the latest change recorded in `managedFields`, or the creation time if there is none.
+pub fn last_modified(metadata: &ObjectMeta) -> Option<&Time> {
+    metadata
+        .managed_fields
+        .iter()
+        .flatten()
+        .filter_map(|entry| entry.time.as_ref())
+        .max_by_key(|time| time.0)
+        .or(metadata.creation_timestamp.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+    use k8s_openapi::chrono::{Duration, Utc};
+
+    use crate::age::format_age;
+
+    #[test]
+    fn largest_whole_unit() {
+        let now = Utc::now();
+        assert_eq!(format_age(&Time(now - Duration::hours(50)), now), "2d");
+        assert_eq!(format_age(&Time(now - Duration::minutes(90)), now), "1h");
+        assert_eq!(format_age(&Time(now - Duration::seconds(42)), now), "42s");
+        assert_eq!(format_age(&Time(now + Duration::seconds(5)), now), "0s");
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use kube::Client;
+use serde::Serialize;
+
+use crate::orphans::references::{find_object_references, ObjectRef, ObjectReferences};
+
+/// Exit status when an object is still in use, distinct from the status of failed runs.
+pub const IN_USE_EXIT_CODE: i32 = 2;
+
+/// Checks whether the `objects` are safe to delete, i.e. not referenced by anything in the `namespace`.
+pub async fn check_deletion(
+    client: &Client,
+    namespace: &str,
+    objects: &[ObjectRef],
+) -> Result<DeletionCheck> {
+    let references = find_object_references(client, namespace, objects).await?;
+    Ok(check(references))
+}
+
+/// Splits `ConfigMap`s and `Secret`s into those safe to delete and those still in use.
+/// Any reference blocks the deletion, including optional ones, and so does any rule keeping
+/// the object in use, e.g. a Helm release `Secret`.
+pub fn check(references: Vec<ObjectReferences>) -> DeletionCheck {
+    let (referenced, deletable): (Vec<ObjectReferences>, Vec<ObjectReferences>) = references
+        .into_iter()
+        .partition(|object| !object.references.is_empty() || object.rule.is_some());
+    DeletionCheck {
+        deletable: deletable.into_iter().map(|object| object.object).collect(),
+        referenced,
+    }
+}
+
+#[derive(Serialize)]
+pub struct DeletionCheck {
+    pub deletable: Vec<String>,
+    /// Objects still in use, with their referrers or the rule keeping them.
+    pub referenced: Vec<ObjectReferences>,
+}
+
+impl DeletionCheck {
+    pub fn is_safe(&self) -> bool {
+        self.referenced.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::can_delete::check;
+    use crate::orphans::references::{ObjectReferences, Reference};
+
+    #[test]
+    fn referenced_objects_block_deletion() {
+        let object = |object: &str, references: Vec<Reference>| ObjectReferences {
+            object: object.to_string(),
+            exists: true,
+            references,
+            rule: None,
+        };
+        let reference = Reference {
+            kind: "Deployment".to_string(),
+            name: "app".to_string(),
+            owners: Vec::new(),
+            container: None,
+            path: "spec.template.spec.volumes[0].secret".to_string(),
+        };
+
+        let deletion_check = check(vec![
+            object("ConfigMap/foo", Vec::new()),
+            object("Secret/bar", vec![reference.clone()]),
+        ]);
+        assert!(!deletion_check.is_safe());
+        assert_eq!(deletion_check.deletable, vec!["ConfigMap/foo".to_string()]);
+        assert_eq!(deletion_check.referenced.len(), 1);
+        assert_eq!(deletion_check.referenced[0].object, "Secret/bar");
+        assert_eq!(deletion_check.referenced[0].references, vec![reference]);
+
+        assert!(check(vec![object("ConfigMap/foo", Vec::new())]).is_safe());
+    }
+
+    #[test]
+    fn rules_block_deletion() {
+        let release = ObjectReferences {
+            object: "Secret/sh.helm.release.v1.app.v1".to_string(),
+            exists: true,
+            references: Vec::new(),
+            rule: Some("Helm release"),
+        };
+
+        let deletion_check = check(vec![release]);
+        assert!(!deletion_check.is_safe());
+        assert!(deletion_check.deletable.is_empty());
+        assert_eq!(deletion_check.referenced[0].rule, Some("Helm release"));
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::{
+    PersistentVolume, PersistentVolumeClaim, PersistentVolumeClaimSpec, Pod, PodSpec,
+    ReplicationController,
+};
+use k8s_openapi::api::networking::v1::{Ingress, IngressClass};
+use k8s_openapi::api::node::v1::RuntimeClass;
+use k8s_openapi::api::scheduling::v1::PriorityClass;
+use k8s_openapi::api::storage::v1::StorageClass;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::Client;
+use serde::Serialize;
+
+use crate::orphans::extend_with;
+use crate::resources::{list_cluster_resource, list_resource_in_all_namespaces};
+
+const DEFAULT_STORAGE_CLASS_ANNOTATION: &str = "storageclass.kubernetes.io/is-default-class";
+const DEFAULT_INGRESS_CLASS_ANNOTATION: &str = "ingressclass.kubernetes.io/is-default-class";
+const LEGACY_INGRESS_CLASS_ANNOTATION: &str = "kubernetes.io/ingress.class";
+/// Prefix of built-in `PriorityClass`es, which are never reported.
+const SYSTEM_PRIORITY_CLASS_PREFIX: &str = "system-";
+
+pub async fn find_cluster_orphans(client: &Client) -> Result<ClusterOrphans> {
+    let (
+        storage_classes,
+        ingress_classes,
+        priority_classes,
+        runtime_classes,
+        persistent_volumes,
+        persistent_volume_claims,
+        ingresses,
+    ) = tokio::try_join!(
+        list_cluster_resource::<StorageClass>(client),
+        list_cluster_resource::<IngressClass>(client),
+        list_cluster_resource::<PriorityClass>(client),
+        list_cluster_resource::<RuntimeClass>(client),
+        list_cluster_resource::<PersistentVolume>(client),
+        list_resource_in_all_namespaces::<PersistentVolumeClaim>(client),
+        list_resource_in_all_namespaces::<Ingress>(client)
+    )?;
+
+    let (
+        deployments,
+        replicasets,
+        statefulsets,
+        daemonsets,
+        jobs,
+        cronjobs,
+        replication_controllers,
+        pods,
+    ) = tokio::try_join!(
+        list_resource_in_all_namespaces::<Deployment>(client),
+        list_resource_in_all_namespaces::<ReplicaSet>(client),
+        list_resource_in_all_namespaces::<StatefulSet>(client),
+        list_resource_in_all_namespaces::<DaemonSet>(client),
+        list_resource_in_all_namespaces::<Job>(client),
+        list_resource_in_all_namespaces::<CronJob>(client),
+        list_resource_in_all_namespaces::<ReplicationController>(client),
+        list_resource_in_all_namespaces::<Pod>(client)
+    )?;
+    let mut pod_specs: Vec<&PodSpec> = Vec::new();
+
+    extend_with(&mut pod_specs, &deployments);
+    extend_with(&mut pod_specs, &replicasets);
+    extend_with(&mut pod_specs, &statefulsets);
+    extend_with(&mut pod_specs, &daemonsets);
+    extend_with(&mut pod_specs, &jobs);
+    extend_with(&mut pod_specs, &cronjobs);
+    extend_with(&mut pod_specs, &replication_controllers);
+    extend_with(&mut pod_specs, &pods);
+
+    Ok(ClusterOrphans {
+        storage_classes: find_unused_storage_classes(
+            &storage_classes,
+            &persistent_volumes,
+            &persistent_volume_claims,
+            &statefulsets,
+        ),
+        ingress_classes: find_unused_ingress_classes(&ingress_classes, &ingresses),
+        priority_classes: find_unused_priority_classes(&priority_classes, &pod_specs),
+        runtime_classes: find_unused_runtime_classes(&runtime_classes, &pod_specs),
+    })
+}
+
+/// Finds `StorageClass`es not used by any `PersistentVolume`, `PersistentVolumeClaim` or `StatefulSet`
+/// volume claim template. Claims without a storage class use the default `StorageClass`, while
+/// an empty `storageClassName` requests no class at all.
+fn find_unused_storage_classes(
+    storage_classes: &[StorageClass],
+    persistent_volumes: &[PersistentVolume],
+    persistent_volume_claims: &[PersistentVolumeClaim],
+    statefulsets: &[StatefulSet],
+) -> HashSet<String> {
+    // Only claims get the default class assigned, volumes without a class have none.
+    let pv_classes = persistent_volumes
+        .iter()
+        .filter_map(|pv| pv.spec.as_ref()?.storage_class_name.as_deref())
+        .filter(|class| !class.is_empty())
+        .map(Some);
+    let pvc_classes = persistent_volume_claims
+        .iter()
+        .filter_map(|pvc| claim_storage_class(pvc.spec.as_ref()));
+    let template_classes = statefulsets
+        .iter()
+        .filter_map(|statefulset| statefulset.spec.as_ref())
+        .flat_map(|spec| spec.volume_claim_templates.iter().flatten())
+        .filter_map(|pvc| claim_storage_class(pvc.spec.as_ref()));
+
+    find_unused(
+        storage_classes.iter().map(|class| {
+            let is_default =
+                is_annotated_default(&class.metadata, DEFAULT_STORAGE_CLASS_ANNOTATION);
+            (&class.metadata, is_default)
+        }),
+        pv_classes.chain(pvc_classes).chain(template_classes),
+    )
+}
+
+/// Storage class used by a claim: `Some(None)` for the default class, `None` for an explicitly empty class.
+fn claim_storage_class(spec: Option<&PersistentVolumeClaimSpec>) -> Option<Option<&str>> {
+    match spec.and_then(|spec| spec.storage_class_name.as_deref()) {
+        Some("") => None,
+        class => Some(class),
+    }
+}
+
+/// Finds `IngressClass`es not named by any `Ingress`, either by `ingressClassName` or by the legacy
+/// `kubernetes.io/ingress.class` annotation. Ingresses without a class use the default `IngressClass`.
+fn find_unused_ingress_classes(
+    ingress_classes: &[IngressClass],
+    ingresses: &[Ingress],
+) -> HashSet<String> {
+    let used_classes = ingresses.iter().map(|ingress| {
+        ingress
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.ingress_class_name.as_deref())
+            .or_else(|| {
+                ingress
+                    .metadata
+                    .annotations
+                    .as_ref()?
+                    .get(LEGACY_INGRESS_CLASS_ANNOTATION)
+                    .map(String::as_str)
+            })
+    });
+
+    find_unused(
+        ingress_classes.iter().map(|class| {
+            let is_default =
+                is_annotated_default(&class.metadata, DEFAULT_INGRESS_CLASS_ANNOTATION);
+            (&class.metadata, is_default)
+        }),
+        used_classes,
+    )
+}
+
+/// Finds `PriorityClass`es not referenced by any `priorityClassName`. The `globalDefault` class is used
+/// by pods without a `priorityClassName`. Built-in `system-` classes are never reported.
+fn find_unused_priority_classes(
+    priority_classes: &[PriorityClass],
+    pod_specs: &[&PodSpec],
+) -> HashSet<String> {
+    let mut unused = find_unused(
+        priority_classes
+            .iter()
+            .map(|class| (&class.metadata, class.global_default == Some(true))),
+        pod_specs
+            .iter()
+            .map(|pod_spec| pod_spec.priority_class_name.as_deref()),
+    );
+    unused.retain(|name| !name.starts_with(SYSTEM_PRIORITY_CLASS_PREFIX));
+    unused
+}
+
+/// Finds `RuntimeClass`es not referenced by any `runtimeClassName`.
+fn find_unused_runtime_classes(
+    runtime_classes: &[RuntimeClass],
+    pod_specs: &[&PodSpec],
+) -> HashSet<String> {
+    find_unused(
+        runtime_classes.iter().map(|class| (&class.metadata, false)),
+        pod_specs
+            .iter()
+            .filter_map(|pod_spec| pod_spec.runtime_class_name.as_deref())
+            .map(Some),
+    )
+}
+
+/// Finds names of `classes`, given with whether they are the default class, not present in `used_classes`.
+/// A `None` in `used_classes` stands for a user of the default class.
+fn find_unused<'a>(
+    classes: impl Iterator<Item = (&'a ObjectMeta, bool)>,
+    used_classes: impl Iterator<Item = Option<&'a str>>,
+) -> HashSet<String> {
+    let used_classes: HashSet<Option<&str>> = used_classes.collect();
+
+    classes
+        .filter(|(_, is_default)| !(*is_default && used_classes.contains(&None)))
+        .filter_map(|(metadata, _)| metadata.name.as_deref())
+        .filter(|name| !used_classes.contains(&Some(*name)))
+        .map(String::from)
+        .collect()
+}
+
+fn is_annotated_default(metadata: &ObjectMeta, default_annotation: &str) -> bool {
+    metadata
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get(default_annotation))
+        .is_some_and(|value| value == "true")
+}
+
+#[derive(Serialize)]
+pub struct ClusterOrphans {
+    #[serde(rename = "storageclasses")]
+    pub storage_classes: HashSet<String>,
+    #[serde(rename = "ingressclasses")]
+    pub ingress_classes: HashSet<String>,
+    #[serde(rename = "priorityclasses")]
+    pub priority_classes: HashSet<String>,
+    #[serde(rename = "runtimeclasses")]
+    pub runtime_classes: HashSet<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::api::core::v1::{
+        PersistentVolume, PersistentVolumeClaim, PersistentVolumeClaimSpec, PersistentVolumeSpec,
+    };
+    use k8s_openapi::api::storage::v1::StorageClass;
+    use kube::api::ObjectMeta;
+
+    use crate::cluster_orphans::find_unused_storage_classes;
+
+    fn storage_class(name: &str, default: bool) -> StorageClass {
+        StorageClass {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                annotations: default.then(|| {
+                    BTreeMap::from([(
+                        "storageclass.kubernetes.io/is-default-class".to_string(),
+                        "true".to_string(),
+                    )])
+                }),
+                ..ObjectMeta::default()
+            },
+            ..StorageClass::default()
+        }
+    }
+
+    fn claim(storage_class_name: Option<&str>) -> PersistentVolumeClaim {
+        PersistentVolumeClaim {
+            spec: Some(PersistentVolumeClaimSpec {
+                storage_class_name: storage_class_name.map(String::from),
+                ..PersistentVolumeClaimSpec::default()
+            }),
+            ..PersistentVolumeClaim::default()
+        }
+    }
+
+    #[test]
+    fn storage_classes_used_explicitly_or_by_default() {
+        let storage_classes = vec![
+            storage_class("local-path", true),
+            storage_class("fast", false),
+            storage_class("unused", false),
+        ];
+        let claims = vec![claim(None), claim(Some("fast"))];
+
+        let unused = find_unused_storage_classes(&storage_classes, &[], &claims, &[]);
+        assert_eq!(unused.len(), 1);
+        assert!(unused.contains("unused"));
+
+        // Volumes without a class and claims requesting no class don't use the default class.
+        let volumes = vec![PersistentVolume {
+            spec: Some(PersistentVolumeSpec::default()),
+            ..PersistentVolume::default()
+        }];
+        let claims = vec![claim(Some("")), claim(Some("fast"))];
+        let unused = find_unused_storage_classes(&storage_classes, &volumes, &claims, &[]);
+        assert_eq!(unused.len(), 2);
+        assert!(unused.contains("local-path"));
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use kube::api::{ApiResource, GroupVersionKind};
+use kube::Client;
+use serde::Serialize;
+
+use crate::resources::{
+    count_resource_instances, list_cluster_resource, list_resource_in_all_namespaces,
+};
+
+/// Domain labels of API groups too generic to identify a controller, e.g. `io` in `cert-manager.io`.
+const GENERIC_GROUP_LABELS: [&str; 12] = [
+    "io",
+    "com",
+    "org",
+    "net",
+    "dev",
+    "sh",
+    "cloud",
+    "k8s",
+    "x-k8s",
+    "kubernetes",
+    "api",
+    "apis",
+];
+const DEPLOYMENT_NAME_LABELS: [&str; 3] =
+    ["app.kubernetes.io/name", "app.kubernetes.io/part-of", "app"];
+/// Number of CRDs whose instances are counted concurrently.
+const CONCURRENT_COUNTS: usize = 8;
+
+/// Lists `CustomResourceDefinition`s with their served versions and a number of instances, flagging
+/// definitions without any instances and definitions whose controller `Deployment` seems to be missing.
+/// Definitions whose instances can't be counted, e.g. when forbidden, are reported with the error.
+pub async fn find_crd_orphans(client: &Client) -> Result<CrdOrphans> {
+    let (crds, deployments) = tokio::try_join!(
+        list_cluster_resource::<CustomResourceDefinition>(client),
+        list_resource_in_all_namespaces::<Deployment>(client)
+    )?;
+
+    let deployment_names = deployment_names(&deployments);
+    let mut usages: Vec<CrdUsage> = stream::iter(
+        crds.iter()
+            .filter_map(|crd| crd_usage(client, crd, &deployment_names)),
+    )
+    .buffer_unordered(CONCURRENT_COUNTS)
+    .collect()
+    .await;
+
+    usages.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(CrdOrphans {
+        unused: usages
+            .iter()
+            .filter(|usage| usage.instances == Some(0))
+            .map(|usage| usage.name.clone())
+            .collect(),
+        without_controller: usages
+            .iter()
+            .filter(|usage| usage.controller_missing)
+            .map(|usage| usage.name.clone())
+            .collect(),
+        custom_resource_definitions: usages,
+    })
+}
+
+/// Counts instances of the `crd`. Instances are the same objects regardless of the version they are
+/// served at, so they are counted once, at the first served version that can be listed. The storage
+/// version is tried first, the others when it fails, e.g. on a broken conversion webhook.
+fn crd_usage<'a>(
+    client: &'a Client,
+    crd: &'a CustomResourceDefinition,
+    deployment_names: &'a HashSet<String>,
+) -> Option<impl std::future::Future<Output = CrdUsage> + 'a> {
+    let name = crd.metadata.name.clone()?;
+    let spec = &crd.spec;
+    let versions: Vec<String> = spec
+        .versions
+        .iter()
+        .filter(|version| version.served)
+        .map(|version| version.name.clone())
+        .collect();
+    let mut served_versions: Vec<_> = spec
+        .versions
+        .iter()
+        .filter(|version| version.served)
+        .collect();
+    if served_versions.is_empty() {
+        return None;
+    }
+    served_versions.sort_by_key(|version| !version.storage);
+    let api_resources: Vec<ApiResource> = served_versions
+        .iter()
+        .map(|version| {
+            ApiResource::from_gvk_with_plural(
+                &GroupVersionKind::gvk(&spec.group, &version.name, &spec.names.kind),
+                &spec.names.plural,
+            )
+        })
+        .collect();
+    let controller_missing = !has_controller(&spec.group, deployment_names);
+
+    Some(async move {
+        let mut instances = None;
+        let mut error = None;
+        for api_resource in &api_resources {
+            match count_resource_instances(client, api_resource).await {
+                Ok(count) => {
+                    instances = Some(count);
+                    error = None;
+                    break;
+                }
+                Err(count_error) => {
+                    error.get_or_insert_with(|| format!("{:#}", anyhow::Error::from(count_error)));
+                }
+            }
+        }
+        CrdUsage {
+            name,
+            versions,
+            instances,
+            error,
+            controller_missing,
+        }
+    })
+}
+
+/// Names, namespaces and name labels of all `Deployment`s, lowercase.
+fn deployment_names(deployments: &[Deployment]) -> HashSet<String> {
+    deployments
+        .iter()
+        .flat_map(|deployment| {
+            let labels = deployment.metadata.labels.as_ref();
+            let name_labels = DEPLOYMENT_NAME_LABELS
+                .iter()
+                .filter_map(move |label| labels?.get(*label).map(String::as_str));
+            [
+                deployment.metadata.name.as_deref(),
+                deployment.metadata.namespace.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .chain(name_labels)
+        })
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Guesses whether a controller of the API `group` is deployed. Any distinctive label of the group,
+/// e.g. `cert-manager` in `cert-manager.io` or `monitoring` in `monitoring.coreos.com`, contained
+/// in a `Deployment`'s name, namespace or name label is considered a match.
+///
+/// This is a heuristic: controllers deployed under unrelated names, e.g.
+/// `argocd-application-controller` for `argoproj.io`, or as `StatefulSet`s or `DaemonSet`s are not
+/// found, so the result is only a hint to look for the controller.
+fn has_controller(group: &str, deployment_names: &HashSet<String>) -> bool {
+    let group = group.to_lowercase();
+    let group_labels: Vec<&str> = group
+        .split('.')
+        .filter(|label| !GENERIC_GROUP_LABELS.contains(label))
+        .collect();
+
+    group_labels.is_empty()
+        || deployment_names.iter().any(|deployment_name| {
+            group_labels
+                .iter()
+                .any(|label| deployment_name.contains(label))
+        })
+}
+
+#[derive(Serialize)]
+pub struct CrdOrphans {
+    #[serde(rename = "customresourcedefinitions")]
+    pub custom_resource_definitions: Vec<CrdUsage>,
+    pub unused: HashSet<String>,
+    pub without_controller: HashSet<String>,
+}
+
+#[derive(Serialize)]
+pub struct CrdUsage {
+    pub name: String,
+    pub versions: Vec<String>,
+    /// Number of instances, unless they couldn't be counted.
+    pub instances: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Whether no `Deployment` seems to run a controller for the API group, a guess by name.
+    pub controller_missing: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::crds::has_controller;
+
+    #[test]
+    fn controller_found_by_group_labels() {
+        let deployment_names =
+            HashSet::from(["cert-manager-webhook".to_string(), "monitoring".to_string()]);
+
+        assert!(has_controller("cert-manager.io", &deployment_names));
+        assert!(has_controller("monitoring.coreos.com", &deployment_names));
+        assert!(!has_controller("argoproj.io", &deployment_names));
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::Result;
+use k8s_openapi::api::core::v1::{
+    ConfigMap, PersistentVolumeClaim, PodSpec, Secret, ServiceAccount,
+};
+use k8s_openapi::api::node::v1::RuntimeClass;
+use k8s_openapi::api::scheduling::v1::PriorityClass;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::Client;
+use serde::Serialize;
+
+use crate::orphans::references::pod_spec_references;
+use crate::resources::{
+    list_optional_cluster_resource_metadata, list_resource, list_resource_metadata,
+};
+use crate::workloads::{get_pod_spec, WorkloadRef};
+
+const DEFAULT_SERVICE_ACCOUNT: &str = "default";
+
+pub async fn find_dependencies(
+    client: &Client,
+    namespace: &str,
+    workload: &WorkloadRef,
+) -> Result<Dependencies> {
+    let pod_spec = get_pod_spec(client, namespace, workload).await?;
+    // Only metadata is listed, as only names are checked. Secret payloads are never fetched.
+    let (
+        cfgmaps,
+        secrets,
+        persistent_volume_claims,
+        service_accounts,
+        priority_classes,
+        runtime_classes,
+    ) = tokio::try_join!(
+        list_resource_metadata::<ConfigMap>(client, namespace),
+        list_resource_metadata::<Secret>(client, namespace),
+        list_resource_metadata::<PersistentVolumeClaim>(client, namespace),
+        list_resource::<ServiceAccount>(client, namespace),
+        list_optional_cluster_resource_metadata::<PriorityClass>(client),
+        list_optional_cluster_resource_metadata::<RuntimeClass>(client)
+    )?;
+
+    let mut existing = Existing::default();
+    existing.extend("ConfigMap", Some(&cfgmaps));
+    existing.extend("Secret", Some(&secrets));
+    existing.extend("PersistentVolumeClaim", Some(&persistent_volume_claims));
+    existing.extend(
+        "ServiceAccount",
+        Some(service_accounts.iter().map(|sa| &sa.metadata)),
+    );
+    existing.extend("PriorityClass", priority_classes.as_ref());
+    existing.extend("RuntimeClass", runtime_classes.as_ref());
+
+    Ok(Dependencies {
+        workload: workload.to_string(),
+        dependencies: list_dependencies(
+            &pod_spec,
+            workload.kind.pod_spec_path(),
+            &service_accounts,
+            &existing,
+        ),
+    })
+}
+
+/// Names of existing objects by kind. Kinds the user can't list are unknown.
+#[derive(Default)]
+pub struct Existing<'a> {
+    names: HashSet<(&'static str, &'a str)>,
+    unknown_kinds: HashSet<&'static str>,
+}
+
+impl<'a> Existing<'a> {
+    /// Adds names of objects of given `kind`, or marks the kind as unknown if they couldn't be listed.
+    pub fn extend(
+        &mut self,
+        kind: &'static str,
+        objects: Option<impl IntoIterator<Item = &'a ObjectMeta>>,
+    ) {
+        let Some(objects) = objects else {
+            self.unknown_kinds.insert(kind);
+            return;
+        };
+        let keys = objects
+            .into_iter()
+            .filter_map(|metadata| metadata.name.as_deref())
+            .map(|name| (kind, name));
+        self.names.extend(keys);
+    }
+
+    /// Whether the object is missing, or `None` if objects of its kind couldn't be listed.
+    fn is_missing(&self, kind: &str, name: &str) -> Option<bool> {
+        (!self.unknown_kinds.contains(kind)).then(|| !self.names.contains(&(kind, name)))
+    }
+}
+
+/// Lists `ConfigMap`s, `Secret`s, `PersistentVolumeClaim`s, the `ServiceAccount` with its pull secrets,
+/// `PriorityClass` and `RuntimeClass` the `pod_spec` found at `path` depends on. Dependencies not
+/// among `existing` ones are marked as missing.
+pub fn list_dependencies(
+    pod_spec: &PodSpec,
+    path: &str,
+    service_accounts: &[ServiceAccount],
+    existing: &Existing,
+) -> Vec<Dependency> {
+    let mut dependencies: BTreeMap<(&'static str, String), Dependency> = BTreeMap::new();
+    let mut add = |kind: &'static str, name: &str, path: String, optional: bool| {
+        let dependency = entry(&mut dependencies, existing, kind, name);
+        dependency.optional &= optional;
+        dependency.paths.push(path);
+    };
+
+    for reference in pod_spec_references(pod_spec, path) {
+        let kind = reference.object.kind.as_str();
+        add(
+            kind,
+            &reference.object.name,
+            reference.path,
+            reference.optional,
+        );
+    }
+    for (idx, volume) in pod_spec.volumes.iter().flatten().enumerate() {
+        if let Some(claim) = &volume.persistent_volume_claim {
+            let path = format!("{path}.volumes[{idx}].persistentVolumeClaim");
+            add("PersistentVolumeClaim", &claim.claim_name, path, false);
+        }
+    }
+
+    let service_account = pod_spec
+        .service_account_name
+        .as_deref()
+        .unwrap_or(DEFAULT_SERVICE_ACCOUNT);
+    add(
+        "ServiceAccount",
+        service_account,
+        format!("{path}.serviceAccountName"),
+        false,
+    );
+    if let Some(priority_class) = &pod_spec.priority_class_name {
+        add(
+            "PriorityClass",
+            priority_class,
+            format!("{path}.priorityClassName"),
+            false,
+        );
+    }
+    if let Some(runtime_class) = &pod_spec.runtime_class_name {
+        add(
+            "RuntimeClass",
+            runtime_class,
+            format!("{path}.runtimeClassName"),
+            false,
+        );
+    }
+
+    let pull_secrets = service_accounts
+        .iter()
+        .find(|sa| sa.metadata.name.as_deref() == Some(service_account))
+        .and_then(|sa| sa.image_pull_secrets.as_ref());
+    for (idx, secret) in pull_secrets.into_iter().flatten().enumerate() {
+        let dependency = entry(&mut dependencies, existing, "Secret", &secret.name);
+        dependency.optional = false;
+        dependency
+            .service_account_paths
+            .push(format!("imagePullSecrets[{idx}]"));
+    }
+
+    dependencies.into_values().collect()
+}
+
+fn entry<'a>(
+    dependencies: &'a mut BTreeMap<(&'static str, String), Dependency>,
+    existing: &Existing,
+    kind: &'static str,
+    name: &str,
+) -> &'a mut Dependency {
+    dependencies
+        .entry((kind, name.to_string()))
+        .or_insert_with(|| Dependency {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            missing: existing.is_missing(kind, name),
+            optional: true,
+            paths: Vec::new(),
+            service_account_paths: Vec::new(),
+        })
+}
+
+/// An object a workload depends on, with the JSON paths referencing it.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Dependency {
+    pub kind: String,
+    pub name: String,
+    /// `None` when objects of this kind can't be listed.
+    pub missing: Option<bool>,
+    /// Whether all references are marked `optional`, so pods start without the object.
+    pub optional: bool,
+    pub paths: Vec<String>,
+    /// JSON paths in the workload's `ServiceAccount`, i.e. its `imagePullSecrets`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub service_account_paths: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct Dependencies {
+    pub workload: String,
+    pub dependencies: Vec<Dependency>,
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{
+        Container, EnvFromSource, LocalObjectReference, PersistentVolumeClaimVolumeSource, PodSpec,
+        SecretEnvSource, ServiceAccount, Volume,
+    };
+    use kube::api::ObjectMeta;
+
+    use crate::deps::{list_dependencies, Dependency, Existing};
+
+    fn metadata(name: &str) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            ..ObjectMeta::default()
+        }
+    }
+
+    #[test]
+    fn dependencies_with_missing_ones() {
+        let pod_spec = PodSpec {
+            containers: vec![Container {
+                env_from: Some(vec![EnvFromSource {
+                    secret_ref: Some(SecretEnvSource {
+                        name: "overrides".to_string(),
+                        optional: Some(true),
+                    }),
+                    ..EnvFromSource::default()
+                }]),
+                ..Container::default()
+            }],
+            service_account_name: Some("app".to_string()),
+            priority_class_name: Some("high".to_string()),
+            volumes: Some(vec![Volume {
+                name: "data".to_string(),
+                persistent_volume_claim: Some(PersistentVolumeClaimVolumeSource {
+                    claim_name: "data".to_string(),
+                    ..PersistentVolumeClaimVolumeSource::default()
+                }),
+                ..Volume::default()
+            }]),
+            ..PodSpec::default()
+        };
+        let service_accounts = vec![ServiceAccount {
+            metadata: metadata("app"),
+            image_pull_secrets: Some(vec![LocalObjectReference {
+                name: "registry".to_string(),
+            }]),
+            ..ServiceAccount::default()
+        }];
+        let claims = vec![metadata("data")];
+        let mut existing = Existing::default();
+        existing.extend("Secret", Some(&[]));
+        existing.extend("PersistentVolumeClaim", Some(&claims));
+        existing.extend(
+            "ServiceAccount",
+            Some(service_accounts.iter().map(|sa| &sa.metadata)),
+        );
+        existing.extend("PriorityClass", None::<&Vec<ObjectMeta>>);
+
+        let dependency = |kind: &str, name: &str, missing: Option<bool>, path: &str| Dependency {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            missing,
+            optional: false,
+            paths: vec![path.to_string()],
+            service_account_paths: Vec::new(),
+        };
+        assert_eq!(
+            list_dependencies(
+                &pod_spec,
+                "spec.template.spec",
+                &service_accounts,
+                &existing
+            ),
+            vec![
+                dependency(
+                    "PersistentVolumeClaim",
+                    "data",
+                    Some(false),
+                    "spec.template.spec.volumes[0].persistentVolumeClaim"
+                ),
+                dependency(
+                    "PriorityClass",
+                    "high",
+                    None,
+                    "spec.template.spec.priorityClassName"
+                ),
+                Dependency {
+                    optional: true,
+                    ..dependency(
+                        "Secret",
+                        "overrides",
+                        Some(true),
+                        "spec.template.spec.containers[0].envFrom[0].secretRef"
+                    )
+                },
+                Dependency {
+                    paths: Vec::new(),
+                    service_account_paths: vec!["imagePullSecrets[0]".to_string()],
+                    ..dependency("Secret", "registry", Some(true), "")
+                },
+                dependency(
+                    "ServiceAccount",
+                    "app",
+                    Some(false),
+                    "spec.template.spec.serviceAccountName"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::Client;
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::orphans::{list_namespace_resources, ROOT_CA_CERT};
+use crate::resources::list_resource_in_all_namespaces;
+
+const KEY_LENGTH: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Searches a single `namespace`, or all namespaces when none is given.
+pub async fn find_duplicates(client: &Client, namespace: Option<&str>) -> Result<Duplicates> {
+    let (cfgmaps, secrets) = match namespace {
+        Some(namespace) => {
+            let resources = list_namespace_resources(client, namespace).await?;
+            (resources.cfgmaps, resources.secrets)
+        }
+        None => tokio::try_join!(
+            list_resource_in_all_namespaces::<ConfigMap>(client),
+            list_resource_in_all_namespaces::<Secret>(client)
+        )?,
+    };
+    // Digests are keyed by a random key thrown away after the run, so they can't be used to guess
+    // low-entropy values, e.g. passwords, by hashing candidates.
+    let mut key = [0u8; KEY_LENGTH];
+    getrandom::getrandom(&mut key).map_err(|error| anyhow!("Random key not generated: {error}"))?;
+    Ok(group_duplicates(&cfgmaps, &secrets, &key))
+}
+
+/// Groups `ConfigMap`s and `Secret`s with byte-identical `data` and `binaryData`. Objects without any
+/// data are skipped. Content is compared by its HMAC keyed by the `key`. Only the digest is printed,
+/// never the values.
+pub fn group_duplicates(cfgmaps: &[ConfigMap], secrets: &[Secret], key: &[u8]) -> Duplicates {
+    let cfgmaps = cfgmaps
+        .iter()
+        .filter(|cfgmap| cfgmap.metadata.name.as_deref() != Some(ROOT_CA_CERT))
+        .map(|cfgmap| {
+            let data = cfgmap
+                .data
+                .iter()
+                .flatten()
+                .map(|(key, value)| (key, value.as_bytes()));
+            let binary_data = cfgmap
+                .binary_data
+                .iter()
+                .flatten()
+                .map(|(key, value)| (key, value.0.as_slice()));
+            (&cfgmap.metadata, content_hash(key, data.chain(binary_data)))
+        });
+    let secrets = secrets.iter().map(|secret| {
+        let data = secret
+            .data
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key, value.0.as_slice()));
+        (&secret.metadata, content_hash(key, data))
+    });
+
+    Duplicates {
+        configmaps: group_by_hash(cfgmaps),
+        secrets: group_by_hash(secrets),
+    }
+}
+
+/// Hashes keys and values with their lengths, so that moving bytes between a key and its value
+/// results in a different hash. `None` when there is no data at all.
+fn content_hash<'a>(
+    key: &[u8],
+    entries: impl Iterator<Item = (&'a String, &'a [u8])>,
+) -> Option<String> {
+    let mut entries: Vec<(&String, &[u8])> = entries.collect();
+    if entries.is_empty() {
+        return None;
+    }
+    entries.sort();
+
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for (name, value) in entries {
+        mac.update(&(name.len() as u64).to_be_bytes());
+        mac.update(name.as_bytes());
+        mac.update(&(value.len() as u64).to_be_bytes());
+        mac.update(value);
+    }
+    Some(format!("hmac-sha256:{:x}", mac.finalize().into_bytes()))
+}
+
+fn group_by_hash<'a>(
+    objects: impl Iterator<Item = (&'a ObjectMeta, Option<String>)>,
+) -> Vec<DuplicateGroup> {
+    let mut by_hash: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for (metadata, hash) in objects {
+        let (Some(hash), Some(name)) = (hash, &metadata.name) else {
+            continue;
+        };
+        let namespace = metadata.namespace.as_deref().unwrap_or_default();
+        by_hash
+            .entry(hash)
+            .or_default()
+            .insert(format!("{namespace}/{name}"));
+    }
+
+    let mut groups: Vec<DuplicateGroup> = by_hash
+        .into_iter()
+        .filter(|(_, objects)| objects.len() > 1)
+        .map(|(hash, objects)| DuplicateGroup {
+            hash,
+            objects: objects.into_iter().collect(),
+        })
+        .collect();
+    groups.sort_by(|a, b| a.objects.cmp(&b.objects));
+    groups
+}
+
+/// Objects sharing the same content, identified as `namespace/name`. The `hash` is keyed by a key
+/// thrown away after the run, so it only tells groups of a single report apart.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub objects: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct Duplicates {
+    pub configmaps: Vec<DuplicateGroup>,
+    pub secrets: Vec<DuplicateGroup>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+    use k8s_openapi::ByteString;
+    use kube::api::ObjectMeta;
+
+    use crate::duplicates::group_duplicates;
+
+    fn metadata(namespace: &str, name: &str) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            namespace: Some(namespace.to_string()),
+            ..ObjectMeta::default()
+        }
+    }
+
+    fn cfgmap(namespace: &str, name: &str, key: &str, value: &str) -> ConfigMap {
+        ConfigMap {
+            metadata: metadata(namespace, name),
+            data: Some(BTreeMap::from([(key.to_string(), value.to_string())])),
+            ..ConfigMap::default()
+        }
+    }
+
+    #[test]
+    fn identical_data_grouped_across_namespaces() {
+        let cfgmaps = vec![
+            cfgmap("dev", "settings", "level", "debug"),
+            cfgmap("prod", "settings-copy", "level", "debug"),
+            cfgmap("prod", "settings", "level", "info"),
+            cfgmap("prod", "shifted", "leveld", "ebug"),
+            ConfigMap {
+                metadata: metadata("dev", "empty"),
+                ..ConfigMap::default()
+            },
+            ConfigMap {
+                metadata: metadata("prod", "empty"),
+                ..ConfigMap::default()
+            },
+        ];
+        let secret = |namespace: &str| Secret {
+            metadata: metadata(namespace, "password"),
+            data: Some(BTreeMap::from([(
+                "level".to_string(),
+                ByteString(b"debug".to_vec()),
+            )])),
+            ..Secret::default()
+        };
+        let secrets = vec![secret("dev"), secret("prod")];
+
+        let duplicates = group_duplicates(&cfgmaps, &secrets, b"key");
+
+        assert_eq!(duplicates.configmaps.len(), 1);
+        assert_eq!(
+            duplicates.configmaps[0].objects,
+            vec!["dev/settings".to_string(), "prod/settings-copy".to_string()]
+        );
+        assert_eq!(duplicates.secrets.len(), 1);
+        assert!(duplicates.configmaps[0].hash.starts_with("hmac-sha256:"));
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use k8s_openapi::api::core::v1::{ConfigMap, Container, PodSpec, Secret};
+use kube::Client;
+use serde::Serialize;
+
+use crate::resources::list_resource;
+use crate::workloads::{get_pod_spec, WorkloadRef};
+
+const MASK: &str = "********";
+
+pub async fn resolve_env(
+    client: &Client,
+    namespace: &str,
+    workload: &WorkloadRef,
+    show_secrets: bool,
+) -> Result<Environment> {
+    let (pod_spec, cfgmaps, secrets) = tokio::try_join!(
+        get_pod_spec(client, namespace, workload),
+        async { Ok(list_resource::<ConfigMap>(client, namespace).await?) },
+        async { Ok(list_resource::<Secret>(client, namespace).await?) }
+    )?;
+    Ok(Environment {
+        workload: workload.to_string(),
+        containers: resolve_pod_spec(&pod_spec, &cfgmaps, &secrets, show_secrets),
+    })
+}
+
+/// Resolves the environment of every init and regular container. `envFrom` sources are applied
+/// first, in order, followed by `env` entries. Later definitions override earlier ones.
+pub fn resolve_pod_spec(
+    pod_spec: &PodSpec,
+    cfgmaps: &[ConfigMap],
+    secrets: &[Secret],
+    show_secrets: bool,
+) -> Vec<ContainerEnv> {
+    let cfgmaps: HashMap<&str, &ConfigMap> = cfgmaps
+        .iter()
+        .filter_map(|cfgmap| Some((cfgmap.metadata.name.as_deref()?, cfgmap)))
+        .collect();
+    let secrets: HashMap<&str, &Secret> = secrets
+        .iter()
+        .filter_map(|secret| Some((secret.metadata.name.as_deref()?, secret)))
+        .collect();
+
+    pod_spec
+        .init_containers
+        .iter()
+        .flatten()
+        .chain(&pod_spec.containers)
+        .map(|container| {
+            let mut container_env = resolve_container(container, &cfgmaps, &secrets);
+            if !show_secrets {
+                container_env
+                    .env
+                    .iter_mut()
+                    .filter(|entry| entry.secret && entry.value.is_some())
+                    .for_each(|entry| entry.value = Some(MASK.to_string()));
+            }
+            container_env
+        })
+        .collect()
+}
+
+fn resolve_container(
+    container: &Container,
+    cfgmaps: &HashMap<&str, &ConfigMap>,
+    secrets: &HashMap<&str, &Secret>,
+) -> ContainerEnv {
+    let mut env: Vec<EnvEntry> = Vec::new();
+    let mut missing_sources = Vec::new();
+
+    for env_from in container.env_from.iter().flatten() {
+        let prefix = env_from.prefix.as_deref().unwrap_or_default();
+        if let Some(cfgmap_ref) = &env_from.config_map_ref {
+            let source = format!("ConfigMap/{}", cfgmap_ref.name);
+            match cfgmaps.get(cfgmap_ref.name.as_str()) {
+                None => missing_sources.push(MissingSource {
+                    source,
+                    optional: cfgmap_ref.optional.unwrap_or(false),
+                }),
+                Some(cfgmap) => cfgmap.data.iter().flatten().for_each(|(key, value)| {
+                    define(
+                        &mut env,
+                        EnvEntry::resolved(format!("{prefix}{key}"), value.clone(), &source),
+                    )
+                }),
+            }
+        }
+        if let Some(secret_ref) = &env_from.secret_ref {
+            let source = format!("Secret/{}", secret_ref.name);
+            match secrets.get(secret_ref.name.as_str()) {
+                None => missing_sources.push(MissingSource {
+                    source,
+                    optional: secret_ref.optional.unwrap_or(false),
+                }),
+                Some(secret) => secret.data.iter().flatten().for_each(|(key, value)| {
+                    let value = String::from_utf8_lossy(&value.0).to_string();
+                    let mut entry = EnvEntry::resolved(format!("{prefix}{key}"), value, &source);
+                    entry.secret = true;
+                    define(&mut env, entry)
+                }),
+            }
+        }
+    }
+
+    for env_var in container.env.iter().flatten() {
+        let name = env_var.name.clone();
+        let entry = match &env_var.value_from {
+            None => {
+                let (value, secret) = expand(env_var.value.as_deref().unwrap_or_default(), &env);
+                let mut entry = EnvEntry::resolved(name, value, "literal");
+                entry.secret = secret;
+                entry
+            }
+            Some(value_from) => {
+                if let Some(key_ref) = &value_from.config_map_key_ref {
+                    let value = cfgmaps
+                        .get(key_ref.name.as_str())
+                        .and_then(|cfgmap| cfgmap.data.as_ref()?.get(&key_ref.key))
+                        .cloned();
+                    let source = format!("ConfigMap/{} key {}", key_ref.name, key_ref.key);
+                    EnvEntry::key_ref(name, value, source, key_ref.optional, false)
+                } else if let Some(key_ref) = &value_from.secret_key_ref {
+                    let value = secrets
+                        .get(key_ref.name.as_str())
+                        .and_then(|secret| secret.data.as_ref()?.get(&key_ref.key))
+                        .map(|value| String::from_utf8_lossy(&value.0).to_string());
+                    let source = format!("Secret/{} key {}", key_ref.name, key_ref.key);
+                    EnvEntry::key_ref(name, value, source, key_ref.optional, true)
+                } else if let Some(field_ref) = &value_from.field_ref {
+                    EnvEntry::runtime(name, format!("fieldRef {}", field_ref.field_path))
+                } else if let Some(resource_ref) = &value_from.resource_field_ref {
+                    EnvEntry::runtime(name, format!("resourceFieldRef {}", resource_ref.resource))
+                } else {
+                    EnvEntry::runtime(name, "unknown".to_string())
+                }
+            }
+        };
+        define(&mut env, entry);
+    }
+
+    ContainerEnv {
+        container: container.name.clone(),
+        env,
+        missing_sources,
+    }
+}
+
+/// Replaces an earlier definition of the same variable, if any.
+fn define(env: &mut Vec<EnvEntry>, entry: EnvEntry) {
+    env.retain(|defined| defined.name != entry.name);
+    env.push(entry);
+}
+
+/// Expands `$(VAR)` references to variables defined earlier, leaving unknown references as they are.
+/// `$$` escapes a dollar sign. Also tells whether a `Secret` value was expanded.
+fn expand(value: &str, env: &[EnvEntry]) -> (String, bool) {
+    let mut expanded = String::new();
+    let mut secret = false;
+    let mut rest = value;
+    while let Some(idx) = rest.find('$') {
+        expanded.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        if let Some(escaped) = rest.strip_prefix("$$") {
+            expanded.push('$');
+            rest = escaped;
+            continue;
+        }
+
+        let reference = rest
+            .strip_prefix("$(")
+            .and_then(|reference| Some(&reference[..reference.find(')')?]));
+        let defined = reference.and_then(|reference| {
+            env.iter()
+                .find(|entry| entry.name == reference && entry.value.is_some())
+        });
+        match (reference, defined) {
+            (Some(reference), Some(entry)) => {
+                expanded.push_str(entry.value.as_deref().unwrap_or_default());
+                secret |= entry.secret;
+                rest = &rest[reference.len() + 3..];
+            }
+            _ => {
+                expanded.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    (expanded, secret)
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnvStatus {
+    Resolved,
+    /// Known only once the container runs, e.g. a `fieldRef`.
+    Runtime,
+    /// Referenced `ConfigMap`, `Secret` or key is missing, preventing the container from starting.
+    Unresolved,
+    /// Referenced `ConfigMap`, `Secret` or key is missing and the variable is left out.
+    OptionalMissing,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct EnvEntry {
+    pub name: String,
+    pub value: Option<String>,
+    pub source: String,
+    pub status: EnvStatus,
+    pub secret: bool,
+}
+
+impl EnvEntry {
+    fn resolved(name: String, value: String, source: &str) -> Self {
+        EnvEntry {
+            name,
+            value: Some(value),
+            source: source.to_string(),
+            status: EnvStatus::Resolved,
+            secret: false,
+        }
+    }
+
+    fn key_ref(
+        name: String,
+        value: Option<String>,
+        source: String,
+        optional: Option<bool>,
+        secret: bool,
+    ) -> Self {
+        let status = match (&value, optional.unwrap_or(false)) {
+            (Some(_), _) => EnvStatus::Resolved,
+            (None, true) => EnvStatus::OptionalMissing,
+            (None, false) => EnvStatus::Unresolved,
+        };
+        EnvEntry {
+            name,
+            value,
+            source,
+            status,
+            secret,
+        }
+    }
+
+    fn runtime(name: String, source: String) -> Self {
+        EnvEntry {
+            name,
+            value: None,
+            source,
+            status: EnvStatus::Runtime,
+            secret: false,
+        }
+    }
+}
+
+/// A referenced `ConfigMap`, `Secret` or key that does not exist.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct MissingSource {
+    pub source: String,
+    pub optional: bool,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ContainerEnv {
+    pub container: String,
+    pub env: Vec<EnvEntry>,
+    pub missing_sources: Vec<MissingSource>,
+}
+
+#[derive(Serialize)]
+pub struct Environment {
+    pub workload: String,
+    pub containers: Vec<ContainerEnv>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::api::core::v1::{
+        ConfigMap, ConfigMapEnvSource, ConfigMapKeySelector, Container, EnvFromSource, EnvVar,
+        EnvVarSource, PodSpec, Secret, SecretEnvSource,
+    };
+    use k8s_openapi::ByteString;
+    use kube::api::ObjectMeta;
+
+    use crate::env::{resolve_pod_spec, EnvStatus, MissingSource};
+
+    fn metadata(name: &str) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            ..ObjectMeta::default()
+        }
+    }
+
+    fn literal(name: &str, value: &str) -> EnvVar {
+        EnvVar {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+            ..EnvVar::default()
+        }
+    }
+
+    #[test]
+    fn env_from_prefix_override_and_masking() {
+        let cfgmaps = vec![ConfigMap {
+            metadata: metadata("settings"),
+            data: Some(BTreeMap::from([
+                ("HOST".to_string(), "db".to_string()),
+                ("PORT".to_string(), "5432".to_string()),
+            ])),
+            ..ConfigMap::default()
+        }];
+        let secrets = vec![Secret {
+            metadata: metadata("credentials"),
+            data: Some(BTreeMap::from([(
+                "PASSWORD".to_string(),
+                ByteString(b"hunter2".to_vec()),
+            )])),
+            ..Secret::default()
+        }];
+        let pod_spec = PodSpec {
+            containers: vec![Container {
+                name: "app".to_string(),
+                env_from: Some(vec![
+                    EnvFromSource {
+                        config_map_ref: Some(ConfigMapEnvSource {
+                            name: "settings".to_string(),
+                            ..ConfigMapEnvSource::default()
+                        }),
+                        prefix: Some("DB_".to_string()),
+                        ..EnvFromSource::default()
+                    },
+                    EnvFromSource {
+                        secret_ref: Some(SecretEnvSource {
+                            name: "credentials".to_string(),
+                            ..SecretEnvSource::default()
+                        }),
+                        ..EnvFromSource::default()
+                    },
+                    EnvFromSource {
+                        secret_ref: Some(SecretEnvSource {
+                            name: "removed".to_string(),
+                            optional: Some(true),
+                        }),
+                        ..EnvFromSource::default()
+                    },
+                ]),
+                env: Some(vec![
+                    literal("DB_PORT", "6432"),
+                    literal("URL", "postgres://$(DB_HOST):$(DB_PORT)/$(UNKNOWN)"),
+                    literal("DSN", "user:$(PASSWORD)"),
+                    EnvVar {
+                        name: "LEVEL".to_string(),
+                        value_from: Some(EnvVarSource {
+                            config_map_key_ref: Some(ConfigMapKeySelector {
+                                name: "settings".to_string(),
+                                key: "LEVEL".to_string(),
+                                optional: Some(true),
+                            }),
+                            ..EnvVarSource::default()
+                        }),
+                        ..EnvVar::default()
+                    },
+                ]),
+                ..Container::default()
+            }],
+            ..PodSpec::default()
+        };
+
+        let containers = resolve_pod_spec(&pod_spec, &cfgmaps, &secrets, false);
+
+        let env: Vec<(&str, Option<&str>)> = containers[0]
+            .env
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.value.as_deref()))
+            .collect();
+        assert_eq!(
+            env,
+            vec![
+                ("DB_HOST", Some("db")),
+                ("PASSWORD", Some("********")),
+                ("DB_PORT", Some("6432")),
+                ("URL", Some("postgres://db:6432/$(UNKNOWN)")),
+                ("DSN", Some("********")),
+                ("LEVEL", None),
+            ]
+        );
+        assert_eq!(containers[0].env[5].status, EnvStatus::OptionalMissing);
+        assert_eq!(
+            containers[0].missing_sources,
+            vec![MissingSource {
+                source: "Secret/removed".to_string(),
+                optional: true,
+            }]
+        );
+
+        let containers = resolve_pod_spec(&pod_spec, &cfgmaps, &secrets, true);
+        assert_eq!(containers[0].env[4].value.as_deref(), Some("user:hunter2"));
+    }
+}
@@ -1,6 +1,10 @@
-use clap::{builder::PossibleValuesParser, Arg, ArgAction, Command};
+use clap::{builder::PossibleValuesParser, value_parser, Arg, ArgAction, ArgMatches, Command};
 use std::str::FromStr;
 
+use crate::orphans::references::ObjectRef;
+use crate::orphans::OrphanOptions;
+use crate::workloads::WorkloadRef;
+
 pub fn parse_user_input() -> UserArgs {
     let matches = Command::new("KubEx - Kubernetes Explorer")
         .version("0.2.0")
@@ -8,66 +12,448 @@ pub fn parse_user_input() -> UserArgs {
         .about("Discovers unused ConfigMaps and Secrets")
         .subcommand(
             Command::new("orphans")
+                .about("Discovers unused ConfigMaps, Secrets and other namespaced resources")
+                .arg(kubeconfig_arg())
+                .arg(namespace_arg())
+                .arg(output_arg())
+                .arg(
+                    Arg::new("REVISION_HISTORY")
+                        .long("revision-history")
+                        .value_name("REVISIONS")
+                        .help("ReplicaSet revisions scaled to zero kept per Deployment for rollbacks. Older ones are reported as stale.")
+                        .value_parser(value_parser!(usize))
+                        .default_value("3")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("TYPE")
+                        .long("type")
+                        .value_name("SECRET_TYPE")
+                        .help("Only report Secrets of given type, e.g. 'kubernetes.io/tls' or 'tls'. Can be repeated.")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("HELM_HISTORY")
+                        .long("helm-history")
+                        .value_name("REVISIONS")
+                        .help("Helm release revisions kept, including the current one. Older ones are reported as stale.")
+                        .value_parser(value_parser!(usize))
+                        .default_value("10")
+                        .action(ArgAction::Set),
+                )
                 .arg(
-                    Arg::new("KUBECONFIG")
-                        .short('k')
-                        .long("kubeconfig")
-                        .value_name("PATH_TO_KUBECONFIG")
-                        .help("Path to a KUBECONFIG file. When not set, env is used.")
+                    Arg::new("SIZE_THRESHOLD")
+                        .long("size-threshold")
+                        .value_name("PERCENT")
+                        .help("Percentage of the 1 MiB size limit from which ConfigMaps and Secrets are reported as large.")
+                        .value_parser(value_parser!(u8).range(0..=100))
+                        .default_value("50")
                         .action(ArgAction::Set),
                 )
                 .arg(
-                    Arg::new("NAMESPACE")
-                        .short('n')
-                        .long("namespace")
-                        .value_name("NAMESPACE")
-                        .help("Namespace to search in.")
+                    Arg::new("EXPIRY_WARNING")
+                        .long("expiry-warning")
+                        .value_name("DAYS")
+                        .help("Days before expiry from which TLS certificates are reported as expiring.")
+                        .value_parser(value_parser!(i64).range(0..))
+                        .default_value("30")
                         .action(ArgAction::Set),
                 )
                 .arg(
-                    Arg::new("OUTPUT")
-                        .short('o')
-                        .long("output")
-                        .value_name("OUTPUT")
-                        .help("Output format. YAML by default.")
-                        .value_parser(PossibleValuesParser::new(["yaml", "json"]))
-                        .default_value("yaml")
+                    Arg::new("EXPLAIN")
+                        .long("explain")
+                        .help("List references keeping every used ConfigMap and Secret alive.")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("cluster-orphans")
+                .about("Discovers unused StorageClasses, IngressClasses, PriorityClasses and RuntimeClasses")
+                .arg(kubeconfig_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            Command::new("crds")
+                .about("Counts instances of CustomResourceDefinitions, discovering unused ones and ones without a controller")
+                .arg(kubeconfig_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            Command::new("empty-namespaces")
+                .about("Discovers namespaces without workloads, containing default objects only")
+                .arg(kubeconfig_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            Command::new("duplicates")
+                .about("Discovers ConfigMaps and Secrets with identical content")
+                .arg(kubeconfig_arg())
+                .arg(namespace_arg())
+                .arg(output_arg())
+                .arg(
+                    Arg::new("ALL_NAMESPACES")
+                        .short('A')
+                        .long("all-namespaces")
+                        .help("Search in all namespaces, finding duplicates across them.")
+                        .conflicts_with("NAMESPACE")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("secrets-audit")
+                .about("Lists containers exposing Secrets as environment variables and Secret volumes with broad permissions")
+                .arg(kubeconfig_arg())
+                .arg(namespace_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            Command::new("stale-config")
+                .about("Discovers running Pods started before a ConfigMap or Secret they read was modified")
+                .arg(kubeconfig_arg())
+                .arg(namespace_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            Command::new("env")
+                .about("Resolves the effective environment of every container of a Pod or workload")
+                .arg(target_arg())
+                .arg(kubeconfig_arg())
+                .arg(namespace_arg())
+                .arg(output_arg())
+                .arg(
+                    Arg::new("SHOW_SECRETS")
+                        .long("show-secrets")
+                        .help("Show values taken from Secrets instead of masking them.")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("mounts")
+                .about("Lists files containers of a Pod or workload see from ConfigMaps, Secrets, projected and downward API volumes")
+                .arg(target_arg())
+                .arg(kubeconfig_arg())
+                .arg(namespace_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            Command::new("refs")
+                .about("Lists every resource referencing a ConfigMap or Secret")
+                .arg(
+                    Arg::new("OBJECT")
+                        .value_name("KIND/NAME")
+                        .help("ConfigMap or Secret, e.g. 'configmap/foo' or 'secret/bar'.")
+                        .value_parser(ObjectRef::from_str)
+                        .required(true)
                         .action(ArgAction::Set),
+                )
+                .arg(kubeconfig_arg())
+                .arg(namespace_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            Command::new("deps")
+                .about("Lists objects a Pod or workload depends on, marking missing ones")
+                .arg(target_arg())
+                .arg(kubeconfig_arg())
+                .arg(namespace_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            Command::new("tree")
+                .about("Shows the ownership tree of a workload with ConfigMaps and Secrets referenced by every level")
+                .arg(target_arg())
+                .arg(kubeconfig_arg())
+                .arg(namespace_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            Command::new("what-if")
+                .about("Simulates changes to the cluster and reports their impact")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("delete")
+                        .about("Lists ConfigMaps, Secrets and PersistentVolumeClaims that would become orphans once given workloads are deleted")
+                        .arg(
+                            Arg::new("TARGETS")
+                                .value_name("[KIND/]NAME")
+                                .help("Pods or workloads to delete, e.g. 'deployment/app'.")
+                                .value_parser(WorkloadRef::from_str)
+                                .required(true)
+                                .num_args(1..)
+                                .action(ArgAction::Append),
+                        )
+                        .arg(kubeconfig_arg())
+                        .arg(namespace_arg())
+                        .arg(output_arg()),
                 ),
         )
+        .subcommand(
+            Command::new("can-delete")
+                .about("Checks ConfigMaps and Secrets are not referenced before deleting them, exiting non-zero otherwise")
+                .arg(
+                    Arg::new("OBJECTS")
+                        .value_name("KIND/NAME")
+                        .help("ConfigMaps or Secrets, e.g. 'configmap/foo' or 'secret/bar'.")
+                        .value_parser(ObjectRef::from_str)
+                        .required(true)
+                        .num_args(1..)
+                        .action(ArgAction::Append),
+                )
+                .arg(kubeconfig_arg())
+                .arg(namespace_arg())
+                .arg(output_arg()),
+        )
         .get_matches();
 
-    UserArgs::new(
-        matches
-            .get_one::<String>("KUBECONFIG")
-            .map(|arg| arg.to_string()),
-        matches
-            .get_one::<String>("NAMESPACE")
-            .map(|arg| arg.to_string()),
-        matches
-            .get_one::<String>("OUTPUT")
-            .map_or(Output::Yaml, |arg| {
-                Output::from_str(arg).unwrap_or(Output::Yaml)
+    match matches.subcommand() {
+        Some(("orphans", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            namespace(matches),
+            output(matches),
+            Mode::Orphans(OrphanOptions {
+                revision_history: *matches.get_one::<usize>("REVISION_HISTORY").unwrap(),
+                secret_types: matches
+                    .get_many::<String>("TYPE")
+                    .map(|types| types.cloned().collect())
+                    .unwrap_or_default(),
+                helm_history: *matches.get_one::<usize>("HELM_HISTORY").unwrap(),
+                size_threshold: *matches.get_one::<u8>("SIZE_THRESHOLD").unwrap(),
+                expiry_warning: *matches.get_one::<i64>("EXPIRY_WARNING").unwrap(),
+                explain: matches.get_flag("EXPLAIN"),
             }),
-    )
+        ),
+        Some(("cluster-orphans", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            None,
+            output(matches),
+            Mode::ClusterOrphans,
+        ),
+        Some(("crds", matches)) => {
+            UserArgs::new(kubeconfig(matches), None, output(matches), Mode::Crds)
+        }
+        Some(("empty-namespaces", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            None,
+            output(matches),
+            Mode::EmptyNamespaces,
+        ),
+        Some(("duplicates", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            namespace(matches),
+            output(matches),
+            Mode::Duplicates {
+                all_namespaces: matches.get_flag("ALL_NAMESPACES"),
+            },
+        ),
+        Some(("secrets-audit", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            namespace(matches),
+            output(matches),
+            Mode::SecretsAudit,
+        ),
+        Some(("stale-config", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            namespace(matches),
+            output(matches),
+            Mode::StaleConfig,
+        ),
+        Some(("env", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            namespace(matches),
+            output(matches),
+            Mode::Env {
+                target: target(matches),
+                show_secrets: matches.get_flag("SHOW_SECRETS"),
+            },
+        ),
+        Some(("mounts", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            namespace(matches),
+            output(matches),
+            Mode::Mounts {
+                target: target(matches),
+            },
+        ),
+        Some(("refs", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            namespace(matches),
+            output(matches),
+            Mode::Refs {
+                object: matches.get_one::<ObjectRef>("OBJECT").unwrap().clone(),
+            },
+        ),
+        Some(("deps", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            namespace(matches),
+            output(matches),
+            Mode::Deps {
+                target: target(matches),
+            },
+        ),
+        Some(("tree", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            namespace(matches),
+            output(matches),
+            Mode::Tree {
+                target: target(matches),
+            },
+        ),
+        Some(("what-if", matches)) => {
+            let (_, matches) = matches.subcommand().unwrap();
+            UserArgs::new(
+                kubeconfig(matches),
+                namespace(matches),
+                output(matches),
+                Mode::WhatIfDelete {
+                    targets: matches
+                        .get_many::<WorkloadRef>("TARGETS")
+                        .unwrap()
+                        .cloned()
+                        .collect(),
+                },
+            )
+        }
+        Some(("can-delete", matches)) => UserArgs::new(
+            kubeconfig(matches),
+            namespace(matches),
+            output(matches),
+            Mode::CanDelete {
+                objects: matches
+                    .get_many::<ObjectRef>("OBJECTS")
+                    .unwrap()
+                    .cloned()
+                    .collect(),
+            },
+        ),
+        _ => UserArgs::new(
+            None,
+            None,
+            Output::Yaml,
+            Mode::Orphans(OrphanOptions::default()),
+        ),
+    }
+}
+
+fn kubeconfig_arg() -> Arg {
+    Arg::new("KUBECONFIG")
+        .short('k')
+        .long("kubeconfig")
+        .value_name("PATH_TO_KUBECONFIG")
+        .help("Path to a KUBECONFIG file. When not set, env is used.")
+        .action(ArgAction::Set)
+}
+
+fn namespace_arg() -> Arg {
+    Arg::new("NAMESPACE")
+        .short('n')
+        .long("namespace")
+        .value_name("NAMESPACE")
+        .help("Namespace to search in.")
+        .action(ArgAction::Set)
+}
+
+fn output_arg() -> Arg {
+    Arg::new("OUTPUT")
+        .short('o')
+        .long("output")
+        .value_name("OUTPUT")
+        .help("Output format. YAML by default.")
+        .value_parser(PossibleValuesParser::new(["yaml", "json"]))
+        .default_value("yaml")
+        .action(ArgAction::Set)
+}
+
+fn target_arg() -> Arg {
+    Arg::new("TARGET")
+        .value_name("[KIND/]NAME")
+        .help("Pod name, or a workload, e.g. 'deployment/app'.")
+        .value_parser(WorkloadRef::from_str)
+        .required(true)
+        .action(ArgAction::Set)
+}
+
+fn kubeconfig(matches: &ArgMatches) -> Option<String> {
+    matches
+        .get_one::<String>("KUBECONFIG")
+        .map(|arg| arg.to_string())
+}
+
+fn namespace(matches: &ArgMatches) -> Option<String> {
+    matches
+        .get_one::<String>("NAMESPACE")
+        .map(|arg| arg.to_string())
+}
+
+fn target(matches: &ArgMatches) -> WorkloadRef {
+    matches.get_one::<WorkloadRef>("TARGET").unwrap().clone()
+}
+
+fn output(matches: &ArgMatches) -> Output {
+    matches
+        .get_one::<String>("OUTPUT")
+        .map_or(Output::Yaml, |arg| {
+            Output::from_str(arg).unwrap_or(Output::Yaml)
+        })
 }
 
 pub struct UserArgs {
     pub kubeconfig: Option<String>,
     pub namespace: Option<String>,
     pub output: Output,
+    pub mode: Mode,
 }
 
 impl UserArgs {
-    pub fn new(kubeconfig: Option<String>, namespace: Option<String>, output: Output) -> Self {
+    pub fn new(
+        kubeconfig: Option<String>,
+        namespace: Option<String>,
+        output: Output,
+        mode: Mode,
+    ) -> Self {
         UserArgs {
             kubeconfig,
             namespace,
             output,
+            mode,
         }
     }
 }
 
+/// Explorer mode selected by the subcommand.
+pub enum Mode {
+    Orphans(OrphanOptions),
+    ClusterOrphans,
+    Crds,
+    EmptyNamespaces,
+    Duplicates {
+        all_namespaces: bool,
+    },
+    SecretsAudit,
+    StaleConfig,
+    Env {
+        target: WorkloadRef,
+        show_secrets: bool,
+    },
+    Mounts {
+        target: WorkloadRef,
+    },
+    Refs {
+        object: ObjectRef,
+    },
+    Deps {
+        target: WorkloadRef,
+    },
+    Tree {
+        target: WorkloadRef,
+    },
+    WhatIfDelete {
+        targets: Vec<WorkloadRef>,
+    },
+    CanDelete {
+        objects: Vec<ObjectRef>,
+    },
+}
+
 pub enum Output {
     Yaml,
     Json,
@@ -77,10 +463,10 @@ impl FromStr for Output {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        return match s.trim().to_lowercase().as_str() {
+        match s.trim().to_lowercase().as_str() {
             "yaml" => Ok(Output::Yaml),
             "json" => Ok(Output::Json),
             _ => Err("Invalid output format".to_string()),
-        };
+        }
     }
 }
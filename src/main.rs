@@ -3,14 +3,42 @@ use std::convert::TryFrom;
 use anyhow::{Context, Result};
 use kube::config::{KubeConfigOptions, Kubeconfig};
 use kube::{Client, Config};
+use serde::Serialize;
 
-use crate::input::{parse_user_input, Output, UserArgs};
+use crate::can_delete::{check_deletion, IN_USE_EXIT_CODE};
+use crate::cluster_orphans::find_cluster_orphans;
+use crate::crds::find_crd_orphans;
+use crate::deps::find_dependencies;
+use crate::duplicates::find_duplicates;
+use crate::env::resolve_env;
+use crate::input::{parse_user_input, Mode, Output, UserArgs};
+use crate::mounts::find_mounts;
+use crate::namespaces::find_empty_namespaces;
 use crate::orphans::find_orphans;
+use crate::orphans::references::find_object_references;
+use crate::orphans::what_if::what_if_delete;
+use crate::secrets_audit::audit_secrets;
+use crate::stale_config::find_stale_config;
+use crate::tree::find_tree;
 
+mod age;
+mod can_delete;
+mod cluster_orphans;
+mod crds;
+mod deps;
+mod duplicates;
+mod env;
 mod input;
+mod mounts;
+mod namespaces;
 mod orphans;
 mod pod_spec;
 mod resources;
+mod secrets_audit;
+mod selector;
+mod stale_config;
+mod tree;
+mod workloads;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -42,24 +70,143 @@ async fn main() -> Result<()> {
         Some(ns) => ns.as_str(),
     };
 
-    println!(
-        "Searching for unused ConfigMaps and Secrets in the '{}' namespace",
-        &namespace
-    );
-
     let client: Client = Client::try_from(config.clone()).unwrap();
 
-    let orphans = find_orphans(&client, namespace).await?;
+    match &user_args.mode {
+        Mode::Orphans(options) => {
+            println!(
+                "Searching for unused ConfigMaps and Secrets in the '{}' namespace",
+                &namespace
+            );
+            let orphans = find_orphans(&client, namespace, options).await?;
+            print_output(&user_args.output, &orphans);
+        }
+        Mode::ClusterOrphans => {
+            println!("Searching for unused cluster-scoped classes");
+            let orphans = find_cluster_orphans(&client).await?;
+            print_output(&user_args.output, &orphans);
+        }
+        Mode::Crds => {
+            println!("Searching for unused CustomResourceDefinitions");
+            let orphans = find_crd_orphans(&client).await?;
+            print_output(&user_args.output, &orphans);
+        }
+        Mode::EmptyNamespaces => {
+            println!("Searching for namespaces without workloads");
+            let empty_namespaces = find_empty_namespaces(&client).await?;
+            print_output(&user_args.output, &empty_namespaces);
+        }
+        Mode::Duplicates { all_namespaces } => {
+            let namespace = (!all_namespaces).then_some(namespace);
+            match namespace {
+                None => {
+                    println!("Searching for duplicate ConfigMaps and Secrets in all namespaces")
+                }
+                Some(namespace) => println!(
+                    "Searching for duplicate ConfigMaps and Secrets in the '{}' namespace",
+                    namespace
+                ),
+            }
+            let duplicates = find_duplicates(&client, namespace).await?;
+            print_output(&user_args.output, &duplicates);
+        }
+        Mode::SecretsAudit => {
+            println!(
+                "Auditing exposure of Secrets in the '{}' namespace",
+                &namespace
+            );
+            let audit = audit_secrets(&client, namespace).await?;
+            print_output(&user_args.output, &audit);
+        }
+        Mode::StaleConfig => {
+            println!(
+                "Searching for Pods with stale configuration in the '{}' namespace",
+                &namespace
+            );
+            let stale_config = find_stale_config(&client, namespace).await?;
+            print_output(&user_args.output, &stale_config);
+        }
+        Mode::Env {
+            target,
+            show_secrets,
+        } => {
+            println!(
+                "Resolving environment of '{}' in the '{}' namespace",
+                target, &namespace
+            );
+            let environment = resolve_env(&client, namespace, target, *show_secrets).await?;
+            print_output(&user_args.output, &environment);
+        }
+        Mode::Mounts { target } => {
+            println!(
+                "Resolving mounted files of '{}' in the '{}' namespace",
+                target, &namespace
+            );
+            let mounts = find_mounts(&client, namespace, target).await?;
+            print_output(&user_args.output, &mounts);
+        }
+        Mode::Refs { object } => {
+            println!(
+                "Searching for references on '{}' in the '{}' namespace",
+                object, &namespace
+            );
+            let references =
+                find_object_references(&client, namespace, std::slice::from_ref(object)).await?;
+            print_output(&user_args.output, &references[0]);
+        }
+        Mode::Deps { target } => {
+            println!(
+                "Searching for dependencies of '{}' in the '{}' namespace",
+                target, &namespace
+            );
+            let dependencies = find_dependencies(&client, namespace, target).await?;
+            print_output(&user_args.output, &dependencies);
+        }
+        Mode::Tree { target } => {
+            println!(
+                "Resolving ownership tree of '{}' in the '{}' namespace",
+                target, &namespace
+            );
+            let tree = find_tree(&client, namespace, target).await?;
+            print_output(&user_args.output, &tree);
+        }
+        Mode::WhatIfDelete { targets } => {
+            let targets_list: Vec<String> = targets.iter().map(ToString::to_string).collect();
+            println!(
+                "Simulating deletion of '{}' in the '{}' namespace",
+                targets_list.join("', '"),
+                &namespace
+            );
+            let impact = what_if_delete(&client, namespace, targets).await?;
+            print_output(&user_args.output, &impact);
+        }
+        Mode::CanDelete { objects } => {
+            let objects_list: Vec<String> = objects.iter().map(ToString::to_string).collect();
+            // Printed to stderr, so scripts can parse the output.
+            eprintln!(
+                "Checking references on '{}' in the '{}' namespace",
+                objects_list.join("', '"),
+                &namespace
+            );
+            let deletion_check = check_deletion(&client, namespace, objects).await?;
+            print_output(&user_args.output, &deletion_check);
+            if !deletion_check.is_safe() {
+                std::process::exit(IN_USE_EXIT_CODE);
+            }
+        }
+    }
+    Ok(())
+}
 
-    match user_args.output {
+fn print_output<T: Serialize>(output: &Output, value: &T) {
+    match output {
         Output::Yaml => {
-            println!("{}", serde_yaml::to_string(&orphans).unwrap());
+            println!("{}", serde_yaml::to_string(value).unwrap());
         }
         Output::Json => {
-            println!("{}", serde_json::to_string_pretty(&orphans).unwrap());
+            println!("{}", serde_json::to_string_pretty(value).unwrap());
         }
     }
-    Ok(())
 }
 
 #[derive(Debug, thiserror::Error)]
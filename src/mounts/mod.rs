@@ -0,0 +1,561 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::Result;
+use k8s_openapi::api::core::v1::{
+    ConfigMap, Container, DownwardAPIVolumeFile, KeyToPath, PodSpec, Secret, Volume,
+};
+use kube::Client;
+use serde::Serialize;
+
+use crate::env::MissingSource;
+use crate::pod_spec::DEFAULT_VOLUME_MODE;
+use crate::resources::list_resource;
+use crate::workloads::{get_pod_spec, WorkloadRef};
+
+pub async fn find_mounts(
+    client: &Client,
+    namespace: &str,
+    workload: &WorkloadRef,
+) -> Result<Mounts> {
+    let (pod_spec, cfgmaps, secrets) = tokio::try_join!(
+        get_pod_spec(client, namespace, workload),
+        async { Ok(list_resource::<ConfigMap>(client, namespace).await?) },
+        async { Ok(list_resource::<Secret>(client, namespace).await?) }
+    )?;
+    Ok(Mounts {
+        workload: workload.to_string(),
+        containers: mounted_files(&pod_spec, &cfgmaps, &secrets),
+    })
+}
+
+/// Builds a tree of files every init and regular container sees from `ConfigMap`, `Secret`,
+/// projected and downward API volumes. Mounts using `subPathExpr` are skipped, as the path is only
+/// known at runtime.
+pub fn mounted_files(
+    pod_spec: &PodSpec,
+    cfgmaps: &[ConfigMap],
+    secrets: &[Secret],
+) -> Vec<ContainerMounts> {
+    let cfgmaps: HashMap<&str, &ConfigMap> = cfgmaps
+        .iter()
+        .filter_map(|cfgmap| Some((cfgmap.metadata.name.as_deref()?, cfgmap)))
+        .collect();
+    let secrets: HashMap<&str, &Secret> = secrets
+        .iter()
+        .filter_map(|secret| Some((secret.metadata.name.as_deref()?, secret)))
+        .collect();
+
+    pod_spec
+        .init_containers
+        .iter()
+        .flatten()
+        .chain(&pod_spec.containers)
+        .map(|container| container_mounts(container, pod_spec, &cfgmaps, &secrets))
+        .collect()
+}
+
+fn container_mounts(
+    container: &Container,
+    pod_spec: &PodSpec,
+    cfgmaps: &HashMap<&str, &ConfigMap>,
+    secrets: &HashMap<&str, &Secret>,
+) -> ContainerMounts {
+    let mut files = BTreeMap::new();
+    let mut missing_sources = Vec::new();
+    for mount in container.volume_mounts.iter().flatten() {
+        if mount.sub_path_expr.is_some() {
+            continue;
+        }
+        let Some(volume) = pod_spec
+            .volumes
+            .iter()
+            .flatten()
+            .find(|volume| volume.name == mount.name)
+        else {
+            continue;
+        };
+        let Some(volume_files) = volume_files(volume, cfgmaps, secrets, &mut missing_sources)
+        else {
+            continue;
+        };
+
+        let sub_path = mount
+            .sub_path
+            .as_deref()
+            .filter(|sub_path| !sub_path.is_empty());
+        for file in volume_files {
+            let relative_path = match sub_path {
+                None => Some(file.path.as_str()),
+                Some(sub_path) if file.path == sub_path => Some(""),
+                Some(sub_path) => file
+                    .path
+                    .strip_prefix(sub_path)
+                    .and_then(|rest| rest.strip_prefix('/')),
+            };
+            let Some(relative_path) = relative_path else {
+                continue;
+            };
+            let path = format!("{}/{relative_path}", mount.mount_path);
+            insert(
+                &mut files,
+                &path,
+                MountedFile {
+                    source: file.source,
+                    mode: format!("{:04o}", file.mode),
+                    read_only: mount.read_only.unwrap_or(false),
+                    updates_live: sub_path.is_none(),
+                },
+            );
+        }
+    }
+
+    ContainerMounts {
+        container: container.name.clone(),
+        files,
+        missing_sources,
+    }
+}
+
+/// A file of a volume, relative to the volume's root.
+struct VolumeFile {
+    path: String,
+    source: String,
+    mode: i32,
+}
+
+/// Lists files of `ConfigMap`, `Secret`, projected and downward API volumes, `None` for other volumes.
+/// Missing `ConfigMap`s and `Secret`s contribute no files and are added to `missing_sources`.
+fn volume_files(
+    volume: &Volume,
+    cfgmaps: &HashMap<&str, &ConfigMap>,
+    secrets: &HashMap<&str, &Secret>,
+    missing_sources: &mut Vec<MissingSource>,
+) -> Option<Vec<VolumeFile>> {
+    let mut files = Vec::new();
+    if let Some(cfgmap) = &volume.config_map {
+        let mode = cfgmap.default_mode.unwrap_or(DEFAULT_VOLUME_MODE);
+        let projection = Projection::new(&cfgmap.name, &cfgmap.items, cfgmap.optional, mode);
+        files.extend(cfgmap_files(&projection, cfgmaps, missing_sources));
+    } else if let Some(secret) = &volume.secret {
+        let mode = secret.default_mode.unwrap_or(DEFAULT_VOLUME_MODE);
+        let name = secret.secret_name.as_deref().unwrap_or_default();
+        let projection = Projection::new(name, &secret.items, secret.optional, mode);
+        files.extend(secret_files(&projection, secrets, missing_sources));
+    } else if let Some(projected) = &volume.projected {
+        let mode = projected.default_mode.unwrap_or(DEFAULT_VOLUME_MODE);
+        for source in projected.sources.iter().flatten() {
+            if let Some(cfgmap) = &source.config_map {
+                let projection =
+                    Projection::new(&cfgmap.name, &cfgmap.items, cfgmap.optional, mode);
+                files.extend(cfgmap_files(&projection, cfgmaps, missing_sources));
+            }
+            if let Some(secret) = &source.secret {
+                let projection =
+                    Projection::new(&secret.name, &secret.items, secret.optional, mode);
+                files.extend(secret_files(&projection, secrets, missing_sources));
+            }
+            if let Some(downward_api) = &source.downward_api {
+                files.extend(downward_api_files(&downward_api.items, mode));
+            }
+            if let Some(token) = &source.service_account_token {
+                files.push(VolumeFile {
+                    path: token.path.clone(),
+                    source: "serviceAccountToken".to_string(),
+                    mode,
+                });
+            }
+            if let Some(trust_bundle) = &source.cluster_trust_bundle {
+                files.push(VolumeFile {
+                    path: trust_bundle.path.clone(),
+                    source: "clusterTrustBundle".to_string(),
+                    mode,
+                });
+            }
+        }
+    } else if let Some(downward_api) = &volume.downward_api {
+        let mode = downward_api.default_mode.unwrap_or(DEFAULT_VOLUME_MODE);
+        files.extend(downward_api_files(&downward_api.items, mode));
+    } else {
+        return None;
+    }
+    Some(files)
+}
+
+/// A `ConfigMap` or `Secret` projected into a volume.
+struct Projection<'a> {
+    name: &'a str,
+    items: &'a Option<Vec<KeyToPath>>,
+    optional: bool,
+    default_mode: i32,
+}
+
+impl<'a> Projection<'a> {
+    fn new(
+        name: &'a str,
+        items: &'a Option<Vec<KeyToPath>>,
+        optional: Option<bool>,
+        default_mode: i32,
+    ) -> Self {
+        Projection {
+            name,
+            items,
+            optional: optional.unwrap_or(false),
+            default_mode,
+        }
+    }
+}
+
+fn cfgmap_files(
+    projection: &Projection,
+    cfgmaps: &HashMap<&str, &ConfigMap>,
+    missing_sources: &mut Vec<MissingSource>,
+) -> Vec<VolumeFile> {
+    let source = format!("ConfigMap/{}", projection.name);
+    let Some(cfgmap) = cfgmaps.get(projection.name) else {
+        missing_sources.push(MissingSource {
+            source,
+            optional: projection.optional,
+        });
+        return Vec::new();
+    };
+    let keys = cfgmap
+        .data
+        .iter()
+        .flat_map(|data| data.keys())
+        .chain(cfgmap.binary_data.iter().flat_map(|data| data.keys()));
+    key_files(keys, projection, &source, missing_sources)
+}
+
+fn secret_files(
+    projection: &Projection,
+    secrets: &HashMap<&str, &Secret>,
+    missing_sources: &mut Vec<MissingSource>,
+) -> Vec<VolumeFile> {
+    let source = format!("Secret/{}", projection.name);
+    let Some(secret) = secrets.get(projection.name) else {
+        missing_sources.push(MissingSource {
+            source,
+            optional: projection.optional,
+        });
+        return Vec::new();
+    };
+    let keys = secret.data.iter().flat_map(|data| data.keys());
+    key_files(keys, projection, &source, missing_sources)
+}
+
+/// Files of a `ConfigMap` or `Secret`: one per key, unless `items` select and remap keys to paths.
+/// Selected keys missing from the source contribute no files and are added to `missing_sources`.
+fn key_files<'a>(
+    keys: impl Iterator<Item = &'a String>,
+    projection: &Projection,
+    source: &str,
+    missing_sources: &mut Vec<MissingSource>,
+) -> Vec<VolumeFile> {
+    let Some(items) = projection.items else {
+        return keys
+            .map(|key| VolumeFile {
+                path: key.clone(),
+                source: format!("{source} key {key}"),
+                mode: projection.default_mode,
+            })
+            .collect();
+    };
+    let keys: HashSet<&String> = keys.collect();
+    let mut files = Vec::new();
+    for item in items {
+        let source = format!("{source} key {}", item.key);
+        if keys.contains(&item.key) {
+            files.push(VolumeFile {
+                path: item.path.clone(),
+                source,
+                mode: item.mode.unwrap_or(projection.default_mode),
+            });
+        } else {
+            missing_sources.push(MissingSource {
+                source,
+                optional: projection.optional,
+            });
+        }
+    }
+    files
+}
+
+fn downward_api_files(
+    items: &Option<Vec<DownwardAPIVolumeFile>>,
+    default_mode: i32,
+) -> Vec<VolumeFile> {
+    items
+        .iter()
+        .flatten()
+        .map(|item| {
+            let source = match (&item.field_ref, &item.resource_field_ref) {
+                (Some(field_ref), _) => format!("fieldRef {}", field_ref.field_path),
+                (None, Some(resource_ref)) => format!("resourceFieldRef {}", resource_ref.resource),
+                (None, None) => "downwardAPI".to_string(),
+            };
+            VolumeFile {
+                path: item.path.clone(),
+                source,
+                mode: item.mode.unwrap_or(default_mode),
+            }
+        })
+        .collect()
+}
+
+/// Inserts the `file` at the absolute `path`, creating directories along the way. A file colliding
+/// with an earlier file or directory, e.g. a `subPath` mount over a projected key, is left out.
+fn insert(tree: &mut BTreeMap<String, Node>, path: &str, file: MountedFile) {
+    let mut components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    let Some(file_name) = components.pop() else {
+        return;
+    };
+    let mut directory = tree;
+    for component in components {
+        let node = directory
+            .entry(component.to_string())
+            .or_insert_with(|| Node::Directory(BTreeMap::new()));
+        let Node::Directory(children) = node else {
+            return;
+        };
+        directory = children;
+    }
+    directory
+        .entry(file_name.to_string())
+        .or_insert(Node::File(file));
+}
+
+/// A file seen by a container. Files mounted using `subPath` are not updated when their source changes.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct MountedFile {
+    pub source: String,
+    pub mode: String,
+    pub read_only: bool,
+    pub updates_live: bool,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Node {
+    File(MountedFile),
+    Directory(BTreeMap<String, Node>),
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ContainerMounts {
+    pub container: String,
+    pub files: BTreeMap<String, Node>,
+    pub missing_sources: Vec<MissingSource>,
+}
+
+#[derive(Serialize)]
+pub struct Mounts {
+    pub workload: String,
+    pub containers: Vec<ContainerMounts>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::api::core::v1::{
+        ConfigMap, ConfigMapVolumeSource, Container, KeyToPath, PodSpec, SecretVolumeSource,
+        Volume, VolumeMount,
+    };
+    use kube::api::ObjectMeta;
+
+    use crate::env::MissingSource;
+    use crate::mounts::{mounted_files, MountedFile, Node};
+
+    #[test]
+    fn items_remapping_sub_path_and_missing_secret() {
+        let cfgmaps = vec![ConfigMap {
+            metadata: ObjectMeta {
+                name: Some("settings".to_string()),
+                ..ObjectMeta::default()
+            },
+            data: Some(BTreeMap::from([
+                ("app.properties".to_string(), String::new()),
+                ("log.xml".to_string(), String::new()),
+            ])),
+            ..ConfigMap::default()
+        }];
+        let mount = |name: &str, mount_path: &str, sub_path: Option<&str>| VolumeMount {
+            name: name.to_string(),
+            mount_path: mount_path.to_string(),
+            sub_path: sub_path.map(str::to_string),
+            ..VolumeMount::default()
+        };
+        let pod_spec = PodSpec {
+            containers: vec![Container {
+                name: "app".to_string(),
+                volume_mounts: Some(vec![
+                    mount("settings", "/etc/app", None),
+                    mount("settings", "/opt/log.xml", Some("conf/log.xml")),
+                    mount("certs", "/etc/certs", None),
+                ]),
+                ..Container::default()
+            }],
+            volumes: Some(vec![
+                Volume {
+                    name: "settings".to_string(),
+                    config_map: Some(ConfigMapVolumeSource {
+                        name: "settings".to_string(),
+                        default_mode: Some(0o400),
+                        items: Some(vec![
+                            KeyToPath {
+                                key: "app.properties".to_string(),
+                                path: "app.properties".to_string(),
+                                mode: None,
+                            },
+                            KeyToPath {
+                                key: "log.xml".to_string(),
+                                path: "conf/log.xml".to_string(),
+                                mode: Some(0o440),
+                            },
+                        ]),
+                        ..ConfigMapVolumeSource::default()
+                    }),
+                    ..Volume::default()
+                },
+                Volume {
+                    name: "certs".to_string(),
+                    secret: Some(SecretVolumeSource {
+                        secret_name: Some("certs".to_string()),
+                        ..SecretVolumeSource::default()
+                    }),
+                    ..Volume::default()
+                },
+            ]),
+            ..PodSpec::default()
+        };
+
+        let containers = mounted_files(&pod_spec, &cfgmaps, &[]);
+
+        let file = |key: &str, mode: &str, updates_live: bool| {
+            Node::File(MountedFile {
+                source: format!("ConfigMap/settings key {key}"),
+                mode: mode.to_string(),
+                read_only: false,
+                updates_live,
+            })
+        };
+        let directory = |children: Vec<(&str, Node)>| {
+            Node::Directory(
+                children
+                    .into_iter()
+                    .map(|(name, node)| (name.to_string(), node))
+                    .collect(),
+            )
+        };
+        assert_eq!(
+            containers[0].files,
+            BTreeMap::from([
+                (
+                    "etc".to_string(),
+                    directory(vec![(
+                        "app",
+                        directory(vec![
+                            ("app.properties", file("app.properties", "0400", true)),
+                            (
+                                "conf",
+                                directory(vec![("log.xml", file("log.xml", "0440", true))])
+                            ),
+                        ])
+                    )])
+                ),
+                (
+                    "opt".to_string(),
+                    directory(vec![("log.xml", file("log.xml", "0440", false))])
+                ),
+            ])
+        );
+        assert_eq!(
+            containers[0].missing_sources,
+            vec![MissingSource {
+                source: "Secret/certs".to_string(),
+                optional: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn colliding_paths_and_missing_items_key() {
+        let cfgmaps = vec![ConfigMap {
+            metadata: ObjectMeta {
+                name: Some("settings".to_string()),
+                ..ObjectMeta::default()
+            },
+            data: Some(BTreeMap::from([("app.yaml".to_string(), String::new())])),
+            ..ConfigMap::default()
+        }];
+        let pod_spec = PodSpec {
+            containers: vec![Container {
+                name: "app".to_string(),
+                volume_mounts: Some(vec![
+                    VolumeMount {
+                        name: "settings".to_string(),
+                        mount_path: "/etc/app".to_string(),
+                        ..VolumeMount::default()
+                    },
+                    VolumeMount {
+                        name: "settings".to_string(),
+                        mount_path: "/etc/app/config/app.yaml".to_string(),
+                        sub_path: Some("config".to_string()),
+                        ..VolumeMount::default()
+                    },
+                ]),
+                ..Container::default()
+            }],
+            volumes: Some(vec![Volume {
+                name: "settings".to_string(),
+                config_map: Some(ConfigMapVolumeSource {
+                    name: "settings".to_string(),
+                    items: Some(vec![
+                        KeyToPath {
+                            key: "app.yaml".to_string(),
+                            path: "config".to_string(),
+                            mode: None,
+                        },
+                        KeyToPath {
+                            key: "log.yaml".to_string(),
+                            path: "log.yaml".to_string(),
+                            mode: None,
+                        },
+                    ]),
+                    optional: Some(true),
+                    ..ConfigMapVolumeSource::default()
+                }),
+                ..Volume::default()
+            }]),
+            ..PodSpec::default()
+        };
+
+        let containers = mounted_files(&pod_spec, &cfgmaps, &[]);
+
+        let config = Node::File(MountedFile {
+            source: "ConfigMap/settings key app.yaml".to_string(),
+            mode: "0644".to_string(),
+            read_only: false,
+            updates_live: true,
+        });
+        assert_eq!(
+            containers[0].files,
+            BTreeMap::from([(
+                "etc".to_string(),
+                Node::Directory(BTreeMap::from([(
+                    "app".to_string(),
+                    Node::Directory(BTreeMap::from([("config".to_string(), config)]))
+                )]))
+            )])
+        );
+        // The volume is mounted twice, each mount reporting the missing key.
+        let missing_key = || MissingSource {
+            source: "ConfigMap/settings key log.yaml".to_string(),
+            optional: true,
+        };
+        assert_eq!(
+            containers[0].missing_sources,
+            vec![missing_key(), missing_key()]
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::{
+    ConfigMap, LimitRange, Namespace, PersistentVolumeClaim, Pod, ReplicationController,
+    ResourceQuota, Secret, Service, ServiceAccount,
+};
+use k8s_openapi::api::networking::v1::Ingress;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+use k8s_openapi::chrono::{DateTime, Utc};
+use kube::Client;
+use serde::Serialize;
+
+use crate::age::{format_age, last_modified};
+use crate::orphans::tokens::{is_service_account_token, SERVICE_ACCOUNT_NAME_ANNOTATION};
+use crate::orphans::ROOT_CA_CERT;
+use crate::resources::{list_cluster_resource, list_resource_in_all_namespaces};
+
+const DEFAULT_SERVICE_ACCOUNT: &str = "default";
+/// Namespaces managed by Kubernetes itself, which are never reported.
+const SYSTEM_NAMESPACES: [&str; 4] = ["default", "kube-system", "kube-public", "kube-node-lease"];
+
+pub async fn find_empty_namespaces(client: &Client) -> Result<EmptyNamespaces> {
+    let (
+        namespaces,
+        deployments,
+        replicasets,
+        statefulsets,
+        daemonsets,
+        jobs,
+        cronjobs,
+        replication_controllers,
+        pods,
+    ) = tokio::try_join!(
+        list_cluster_resource::<Namespace>(client),
+        list_resource_in_all_namespaces::<Deployment>(client),
+        list_resource_in_all_namespaces::<ReplicaSet>(client),
+        list_resource_in_all_namespaces::<StatefulSet>(client),
+        list_resource_in_all_namespaces::<DaemonSet>(client),
+        list_resource_in_all_namespaces::<Job>(client),
+        list_resource_in_all_namespaces::<CronJob>(client),
+        list_resource_in_all_namespaces::<ReplicationController>(client),
+        list_resource_in_all_namespaces::<Pod>(client)
+    )?;
+
+    let (
+        configmaps,
+        secrets,
+        service_accounts,
+        services,
+        persistent_volume_claims,
+        ingresses,
+        resource_quotas,
+        limit_ranges,
+    ) = tokio::try_join!(
+        list_resource_in_all_namespaces::<ConfigMap>(client),
+        list_resource_in_all_namespaces::<Secret>(client),
+        list_resource_in_all_namespaces::<ServiceAccount>(client),
+        list_resource_in_all_namespaces::<Service>(client),
+        list_resource_in_all_namespaces::<PersistentVolumeClaim>(client),
+        list_resource_in_all_namespaces::<Ingress>(client),
+        list_resource_in_all_namespaces::<ResourceQuota>(client),
+        list_resource_in_all_namespaces::<LimitRange>(client)
+    )?;
+
+    let mut contents = NamespaceContents::default();
+    contents.add_workloads(&deployments);
+    contents.add_workloads(&replicasets);
+    contents.add_workloads(&statefulsets);
+    contents.add_workloads(&daemonsets);
+    contents.add_workloads(&jobs);
+    contents.add_workloads(&cronjobs);
+    contents.add_workloads(&replication_controllers);
+    contents.add_workloads(&pods);
+
+    contents.add_objects(&configmaps, |cfgmap| {
+        cfgmap.metadata.name.as_deref() == Some(ROOT_CA_CERT)
+    });
+    contents.add_objects(&secrets, is_default_service_account_token);
+    contents.add_objects(&service_accounts, |sa| {
+        sa.metadata.name.as_deref() == Some(DEFAULT_SERVICE_ACCOUNT)
+    });
+    contents.add_objects(&services, |_| false);
+    contents.add_objects(&persistent_volume_claims, |_| false);
+    contents.add_objects(&ingresses, |_| false);
+    contents.add_objects(&resource_quotas, |_| true);
+    contents.add_objects(&limit_ranges, |_| true);
+
+    Ok(EmptyNamespaces {
+        namespaces: contents.empty_namespaces(&namespaces, Utc::now()),
+    })
+}
+
+fn is_default_service_account_token(secret: &Secret) -> bool {
+    is_service_account_token(secret)
+        && secret
+            .metadata
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get(SERVICE_ACCOUNT_NAME_ANNOTATION))
+            .is_some_and(|sa| sa == DEFAULT_SERVICE_ACCOUNT)
+}
+
+/// Objects found in each namespace, keyed by namespace name.
+#[derive(Default)]
+struct NamespaceContents<'a> {
+    /// Namespaces with any workload, or with objects not created by default.
+    in_use: HashSet<&'a str>,
+    /// Metadata of default objects, used to determine the last activity in a namespace.
+    default_objects: HashMap<&'a str, Vec<&'a ObjectMeta>>,
+}
+
+impl<'a> NamespaceContents<'a> {
+    fn add_workloads<T>(&mut self, workloads: &'a [T])
+    where
+        T: k8s_openapi::Metadata<Ty = ObjectMeta>,
+    {
+        workloads
+            .iter()
+            .filter_map(|workload| workload.metadata().namespace.as_deref())
+            .for_each(|namespace| {
+                self.in_use.insert(namespace);
+            });
+    }
+
+    fn add_objects<T>(&mut self, objects: &'a [T], is_default: impl Fn(&T) -> bool)
+    where
+        T: k8s_openapi::Metadata<Ty = ObjectMeta>,
+    {
+        for object in objects {
+            let Some(namespace) = object.metadata().namespace.as_deref() else {
+                continue;
+            };
+            if is_default(object) {
+                self.default_objects
+                    .entry(namespace)
+                    .or_default()
+                    .push(object.metadata());
+            } else {
+                self.in_use.insert(namespace);
+            }
+        }
+    }
+
+    /// Lists namespaces without workloads and with default objects only, sorted by name.
+    fn empty_namespaces(
+        &self,
+        namespaces: &[Namespace],
+        now: DateTime<Utc>,
+    ) -> Vec<EmptyNamespace> {
+        let mut empty_namespaces: Vec<EmptyNamespace> = namespaces
+            .iter()
+            .filter_map(|namespace| {
+                let name = namespace.metadata.name.as_deref()?;
+                if SYSTEM_NAMESPACES.contains(&name) || self.in_use.contains(name) {
+                    return None;
+                }
+
+                let default_objects = self.default_objects.get(name).into_iter().flatten();
+                let last_activity = std::iter::once(&namespace.metadata)
+                    .chain(default_objects.copied())
+                    .filter_map(last_modified)
+                    .max_by_key(|time| time.0)
+                    .cloned();
+
+                Some(EmptyNamespace {
+                    name: name.to_string(),
+                    age: namespace
+                        .metadata
+                        .creation_timestamp
+                        .as_ref()
+                        .map(|created| format_age(created, now)),
+                    last_activity,
+                })
+            })
+            .collect();
+        empty_namespaces.sort_by(|a, b| a.name.cmp(&b.name));
+        empty_namespaces
+    }
+}
+
+#[derive(Serialize)]
+pub struct EmptyNamespaces {
+    pub namespaces: Vec<EmptyNamespace>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct EmptyNamespace {
+    pub name: String,
+    pub age: Option<String>,
+    pub last_activity: Option<Time>,
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{ConfigMap, Namespace, Pod};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ManagedFieldsEntry, Time};
+    use k8s_openapi::chrono::{Duration, Utc};
+    use kube::api::ObjectMeta;
+
+    use crate::namespaces::{EmptyNamespace, NamespaceContents};
+
+    fn metadata(name: &str, namespace: Option<&str>) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            namespace: namespace.map(String::from),
+            ..ObjectMeta::default()
+        }
+    }
+
+    #[test]
+    fn namespaces_with_default_objects_only() {
+        let now = Utc::now();
+        let mut root_ca = metadata("kube-root-ca.crt", Some("abandoned"));
+        root_ca.managed_fields = Some(vec![ManagedFieldsEntry {
+            time: Some(Time(now - Duration::days(2))),
+            ..ManagedFieldsEntry::default()
+        }]);
+        let configmaps = vec![
+            ConfigMap {
+                metadata: root_ca,
+                ..ConfigMap::default()
+            },
+            ConfigMap {
+                metadata: metadata("settings", Some("configured")),
+                ..ConfigMap::default()
+            },
+        ];
+        let pods = vec![Pod {
+            metadata: metadata("nginx", Some("running")),
+            ..Pod::default()
+        }];
+        let mut abandoned = metadata("abandoned", None);
+        abandoned.creation_timestamp = Some(Time(now - Duration::days(30)));
+        let namespaces: Vec<Namespace> = [
+            abandoned,
+            metadata("configured", None),
+            metadata("running", None),
+            metadata("default", None),
+        ]
+        .into_iter()
+        .map(|metadata| Namespace {
+            metadata,
+            ..Namespace::default()
+        })
+        .collect();
+
+        let mut contents = NamespaceContents::default();
+        contents.add_workloads(&pods);
+        contents.add_objects(&configmaps, |cfgmap| {
+            cfgmap.metadata.name.as_deref() == Some("kube-root-ca.crt")
+        });
+
+        assert_eq!(
+            contents.empty_namespaces(&namespaces, now),
+            vec![EmptyNamespace {
+                name: "abandoned".to_string(),
+                age: Some("30d".to_string()),
+                last_activity: Some(Time(now - Duration::days(2))),
+            }]
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+use k8s_openapi::chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::Pem;
+
+use crate::orphans::secret_types::secret_type;
+
+const TLS_TYPE: &str = "kubernetes.io/tls";
+const TLS_CERT_KEY: &str = "tls.crt";
+const PEM_CERTIFICATE: &str = "CERTIFICATE";
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CertificateStatus {
+    Valid,
+    Expiring,
+    Expired,
+    Unparsable,
+}
+
+/// The leaf certificate of a `kubernetes.io/tls` `Secret`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TlsCertificate {
+    pub secret: String,
+    pub subject: Option<String>,
+    pub sans: Vec<String>,
+    pub issuer: Option<String>,
+    pub not_after: Option<Time>,
+    pub status: CertificateStatus,
+    pub referenced: bool,
+}
+
+/// Inspects the first certificate in `tls.crt` of every `kubernetes.io/tls` `Secret`. Certificates
+/// expiring within `expiry_warning` are reported as expiring.
+pub fn find_tls_certificates(
+    secrets: &[Secret],
+    secrets_orphans: &HashSet<String>,
+    expiry_warning: Duration,
+    now: DateTime<Utc>,
+) -> Vec<TlsCertificate> {
+    let mut certificates: Vec<TlsCertificate> = secrets
+        .iter()
+        .filter(|secret| secret_type(secret) == TLS_TYPE)
+        .filter_map(|secret| {
+            let name = secret.metadata.name.as_ref()?;
+            let certificate = secret
+                .data
+                .as_ref()
+                .and_then(|data| data.get(TLS_CERT_KEY))
+                .and_then(|pem| {
+                    Pem::iter_from_buffer(&pem.0)
+                        .map_while(Result::ok)
+                        .find(|pem| pem.label == PEM_CERTIFICATE)
+                })
+                .and_then(|pem| parse_certificate(&pem));
+
+            let status = match &certificate {
+                None => CertificateStatus::Unparsable,
+                Some(certificate) if certificate.not_after <= now => CertificateStatus::Expired,
+                Some(certificate) if certificate.not_after <= now + expiry_warning => {
+                    CertificateStatus::Expiring
+                }
+                Some(_) => CertificateStatus::Valid,
+            };
+            let referenced = !secrets_orphans.contains(name);
+            Some(match certificate {
+                None => TlsCertificate {
+                    secret: name.clone(),
+                    subject: None,
+                    sans: Vec::new(),
+                    issuer: None,
+                    not_after: None,
+                    status,
+                    referenced,
+                },
+                Some(certificate) => TlsCertificate {
+                    secret: name.clone(),
+                    subject: Some(certificate.subject),
+                    sans: certificate.sans,
+                    issuer: Some(certificate.issuer),
+                    not_after: Some(Time(certificate.not_after)),
+                    status,
+                    referenced,
+                },
+            })
+        })
+        .collect();
+    certificates.sort_by(|a, b| a.secret.cmp(&b.secret));
+    certificates
+}
+
+struct Certificate {
+    subject: String,
+    sans: Vec<String>,
+    issuer: String,
+    not_after: DateTime<Utc>,
+}
+
+/// Reads the few fields of interest from a PEM-encoded X.509 certificate.
+fn parse_certificate(pem: &Pem) -> Option<Certificate> {
+    let certificate = pem.parse_x509().ok()?;
+    let not_after = DateTime::from_timestamp(certificate.validity().not_after.timestamp(), 0)?;
+    // A malformed extension only loses the SANs, the expiry is still reported.
+    let sans = certificate
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|extension| {
+            extension
+                .value
+                .general_names
+                .iter()
+                .filter_map(format_general_name)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(Certificate {
+        subject: certificate.subject().to_string(),
+        sans,
+        issuer: certificate.issuer().to_string(),
+        not_after,
+    })
+}
+
+/// Formats DNS names, IP addresses, emails and URIs of the subject alternative name extension.
+fn format_general_name(name: &GeneralName) -> Option<String> {
+    match name {
+        GeneralName::DNSName(name) | GeneralName::RFC822Name(name) | GeneralName::URI(name) => {
+            Some(name.to_string())
+        }
+        GeneralName::IPAddress(address) => match address.len() {
+            4 => Some(Ipv4Addr::from(<[u8; 4]>::try_from(*address).ok()?).to_string()),
+            16 => Some(Ipv6Addr::from(<[u8; 16]>::try_from(*address).ok()?).to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+
+    use k8s_openapi::api::core::v1::Secret;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+    use k8s_openapi::chrono::{Duration, TimeZone, Utc};
+    use k8s_openapi::ByteString;
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::certificates::{find_tls_certificates, CertificateStatus, TlsCertificate};
+
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIB4jCCAYigAwIBAgIUVmkRJS1wuXEZ2eQBm+lXXeG8qdMwCgYIKoZIzj0EAwIw
+LDEYMBYGA1UEAwwPYXBwLmV4YW1wbGUuY29tMRAwDgYDVQQKDAdFeGFtcGxlMB4X
+DTI2MTAxODE3NTYxMloXDTI2MTAxOTE3NTYxMlowLDEYMBYGA1UEAwwPYXBwLmV4
+YW1wbGUuY29tMRAwDgYDVQQKDAdFeGFtcGxlMFkwEwYHKoZIzj0CAQYIKoZIzj0D
+AQcDQgAEB7wjntXKTfDnG+khP80GShlL5194S0PFdkCPePalgijYR/Aj705rnfdA
+RJJz+lIUFLS9kxwAwnurNMsxlwszh6OBhzCBhDAdBgNVHQ4EFgQUXHxKwpmxIGR8
+An84nXl0jJay6tYwHwYDVR0jBBgwFoAUXHxKwpmxIGR8An84nXl0jJay6tYwDwYD
+VR0TAQH/BAUwAwEB/zAxBgNVHREEKjAogg9hcHAuZXhhbXBsZS5jb22CD3d3dy5l
+eGFtcGxlLmNvbYcECgAAATAKBggqhkjOPQQDAgNIADBFAiAQo6VaDIVKIKRBLzLa
+Cxg6IT/k1O3JZjaACeUl90jn4gIhAIpjfaqpTYmnPu3yE7S9wftgQr/EBwIw84Lf
+ALxxuC9C
+-----END CERTIFICATE-----
+";
+
+    /// Carries two subject alternative name extensions.
+    const DUPLICATE_SANS_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBoTCCAUegAwIBAgIUIqT3X+/H3mbpI3M7lQ9x8EnNlrwwCgYIKoZIzj0EAwIw
+GjEYMBYGA1UEAwwPZHVwLmV4YW1wbGUuY29tMB4XDTI2MTAxODE5MDUzMVoXDTI2
+MTAxOTE5MDUzMVowGjEYMBYGA1UEAwwPZHVwLmV4YW1wbGUuY29tMFkwEwYHKoZI
+zj0CAQYIKoZIzj0DAQcDQgAEwZwFquXNBixX+Vv4cnHLzX5ylja5CpCZNwD3shZt
+QSE8ziKw9Lwe6S+z2WIGxWg1gvMKRlkx8bgo6Zg3xbSzyqNrMGkwHQYDVR0RBBYE
+FEORETR1P7RME36uCBp3W5rD39zCMB8GA1UdIwQYMBaAFEORETR1P7RME36uCBp3
+W5rD39zCMA8GA1UdEwEB/wQFMAMBAf8wFgYDVR0RBA8wDYILb3RoZXIubG9jYWww
+CgYIKoZIzj0EAwIDSAAwRQIgKje4ZrJvDd4shrvWip5YnJMAfvHbU7f0lDHOEWyB
+X/sCIQC1n++YNJdU4/J8OdMmqUN9fHI2GMkq+hhrCb9SXdPDrQ==
+-----END CERTIFICATE-----
+";
+
+    fn tls_secret(name: &str, certificate: &str) -> Secret {
+        Secret {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            type_: Some("kubernetes.io/tls".to_string()),
+            data: Some(BTreeMap::from([(
+                "tls.crt".to_string(),
+                ByteString(certificate.as_bytes().to_vec()),
+            )])),
+            ..Secret::default()
+        }
+    }
+
+    #[test]
+    fn expired_unreferenced_certificate() {
+        let secrets = vec![tls_secret("app-tls", CERTIFICATE)];
+        let not_after = Utc.with_ymd_and_hms(2026, 10, 19, 17, 56, 12).unwrap();
+        let orphans = HashSet::from(["app-tls".to_string()]);
+
+        let certificates = find_tls_certificates(
+            &secrets,
+            &orphans,
+            Duration::days(30),
+            not_after + Duration::days(1),
+        );
+        assert_eq!(
+            certificates,
+            vec![TlsCertificate {
+                secret: "app-tls".to_string(),
+                subject: Some("CN=app.example.com, O=Example".to_string()),
+                sans: vec![
+                    "app.example.com".to_string(),
+                    "www.example.com".to_string(),
+                    "10.0.0.1".to_string()
+                ],
+                issuer: Some("CN=app.example.com, O=Example".to_string()),
+                not_after: Some(Time(not_after)),
+                status: CertificateStatus::Expired,
+                referenced: false,
+            }]
+        );
+
+        let certificates =
+            find_tls_certificates(&secrets, &HashSet::new(), Duration::days(30), not_after);
+        assert_eq!(certificates[0].status, CertificateStatus::Expired);
+        let certificates = find_tls_certificates(
+            &secrets,
+            &HashSet::new(),
+            Duration::days(30),
+            not_after - Duration::days(2),
+        );
+        assert_eq!(certificates[0].status, CertificateStatus::Expiring);
+        assert!(certificates[0].referenced);
+    }
+
+    #[test]
+    fn expiry_of_certificate_with_malformed_sans() {
+        let secrets = vec![tls_secret("dup-tls", DUPLICATE_SANS_CERTIFICATE)];
+        let not_after = Utc.with_ymd_and_hms(2026, 10, 19, 19, 5, 31).unwrap();
+
+        let certificates = find_tls_certificates(
+            &secrets,
+            &HashSet::new(),
+            Duration::days(30),
+            not_after + Duration::days(1),
+        );
+        assert_eq!(certificates[0].status, CertificateStatus::Expired);
+        assert_eq!(certificates[0].not_after, Some(Time(not_after)));
+        assert!(certificates[0].sans.is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::LazyLock;
+
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use regex::Regex;
+use serde::Serialize;
+
+const HELM_RELEASE_TYPE: &str = "helm.sh/release.v1";
+const HELM_RELEASE_NAME_ANNOTATION: &str = "meta.helm.sh/release-name";
+const HELM_STATUS_LABEL: &str = "status";
+const HELM_DEPLOYED_STATUS: &str = "deployed";
+
+/// Helm stores each revision of a release `<name>` as a `sh.helm.release.v1.<name>.v<revision>` Secret.
+static HELM_RELEASE_SECRET: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^sh\.helm\.release\.v1\.(.+)\.v(\d+)$").unwrap());
+
+/// A Helm release with revisions to clean up, or without any resources left.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct HelmRelease {
+    pub release: String,
+    pub current_revision: u32,
+    pub resources_left: bool,
+    pub stale_revisions: Vec<String>,
+}
+
+pub fn is_release_secret(secret: &Secret) -> bool {
+    secret.type_.as_deref() == Some(HELM_RELEASE_TYPE)
+        && secret
+            .metadata
+            .name
+            .as_deref()
+            .is_some_and(|name| HELM_RELEASE_SECRET.is_match(name))
+}
+
+/// Collects names of Helm releases the `resources` were deployed by.
+pub fn extend_with_releases<'a, T>(releases: &mut HashSet<&'a str>, resources: &'a [T])
+where
+    T: k8s_openapi::Metadata<Ty = ObjectMeta>,
+{
+    let resource_releases = resources.iter().filter_map(|resource| {
+        resource
+            .metadata()
+            .annotations
+            .as_ref()?
+            .get(HELM_RELEASE_NAME_ANNOTATION)
+            .map(String::as_str)
+    });
+    releases.extend(resource_releases);
+}
+
+/// Inspects Helm release storage. The current revision of each release, i.e. the deployed one
+/// or the latest one, is kept. Revisions beyond `history` newest ones are reported as stale.
+/// Releases without any resources in `releases_with_resources` are reported as well, as they
+/// were likely uninstalled without Helm.
+pub fn find_helm_releases(
+    secrets: &[Secret],
+    releases_with_resources: &HashSet<&str>,
+    history: usize,
+) -> Vec<HelmRelease> {
+    let mut revisions_by_release: BTreeMap<&str, Vec<(u32, &Secret)>> = BTreeMap::new();
+    secrets
+        .iter()
+        .filter(|secret| is_release_secret(secret))
+        .for_each(|secret| {
+            let name = secret.metadata.name.as_deref().unwrap_or_default();
+            let Some(captures) = HELM_RELEASE_SECRET.captures(name) else {
+                return;
+            };
+            let (Some(release), Some(Ok(revision))) = (
+                captures.get(1),
+                captures.get(2).map(|revision| revision.as_str().parse()),
+            ) else {
+                return;
+            };
+            revisions_by_release
+                .entry(release.as_str())
+                .or_default()
+                .push((revision, secret));
+        });
+
+    revisions_by_release
+        .into_iter()
+        .filter_map(|(release, mut revisions)| {
+            revisions.sort_by_key(|(revision, _)| std::cmp::Reverse(*revision));
+            let current_revision = revisions
+                .iter()
+                .find(|(_, secret)| is_deployed(secret))
+                .or(revisions.first())
+                .map(|(revision, _)| *revision)?;
+
+            let stale_revisions: Vec<String> = revisions
+                .iter()
+                .filter(|(revision, _)| *revision != current_revision)
+                .skip(history.saturating_sub(1))
+                .filter_map(|(_, secret)| secret.metadata.name.clone())
+                .collect();
+            let resources_left = releases_with_resources.contains(release);
+
+            (!stale_revisions.is_empty() || !resources_left).then(|| HelmRelease {
+                release: release.to_string(),
+                current_revision,
+                resources_left,
+                stale_revisions,
+            })
+        })
+        .collect()
+}
+
+fn is_deployed(secret: &Secret) -> bool {
+    secret
+        .metadata
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get(HELM_STATUS_LABEL))
+        .is_some_and(|status| status == HELM_DEPLOYED_STATUS)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+
+    use k8s_openapi::api::core::v1::Secret;
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::helm::{find_helm_releases, HelmRelease};
+
+    fn release_secret(release: &str, revision: u32, status: &str) -> Secret {
+        Secret {
+            metadata: ObjectMeta {
+                name: Some(format!("sh.helm.release.v1.{release}.v{revision}")),
+                labels: Some(BTreeMap::from([("status".to_string(), status.to_string())])),
+                ..ObjectMeta::default()
+            },
+            type_: Some("helm.sh/release.v1".to_string()),
+            ..Secret::default()
+        }
+    }
+
+    #[test]
+    fn stale_revisions_and_abandoned_releases() {
+        let secrets = vec![
+            release_secret("web", 1, "superseded"),
+            release_secret("web", 2, "superseded"),
+            release_secret("web", 3, "deployed"),
+            release_secret("web", 4, "failed"),
+            release_secret("db", 1, "deployed"),
+            release_secret("cache", 1, "deployed"),
+        ];
+        let releases_with_resources = HashSet::from(["web", "cache"]);
+
+        assert_eq!(
+            find_helm_releases(&secrets, &releases_with_resources, 2),
+            vec![
+                HelmRelease {
+                    release: "db".to_string(),
+                    current_revision: 1,
+                    resources_left: false,
+                    stale_revisions: vec![],
+                },
+                HelmRelease {
+                    release: "web".to_string(),
+                    current_revision: 3,
+                    resources_left: true,
+                    stale_revisions: vec![
+                        "sh.helm.release.v1.web.v2".to_string(),
+                        "sh.helm.release.v1.web.v1".to_string(),
+                    ],
+                },
+            ]
+        );
+    }
+}
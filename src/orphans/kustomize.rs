@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::LazyLock;
+
+use k8s_openapi::api::core::v1::ConfigMap;
+use regex::Regex;
+use serde::Serialize;
+
+/// Kustomize's `configMapGenerator` appends a dash and a 10 characters long content hash to the name.
+/// The hash is encoded using hexadecimal digits, with vowels and easily confused digits replaced.
+static KUSTOMIZE_HASH_SUFFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+)-[2456789bcdfghkmt]{10}$").unwrap());
+
+/// Generations of a kustomize-generated `ConfigMap` sharing the same base name. Superseded generations
+/// are no longer referenced, while a newer generation is.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Generations {
+    pub base_name: String,
+    pub referenced: Vec<String>,
+    pub superseded: Vec<String>,
+}
+
+/// Groups kustomize-generated `ConfigMap`s by base name and moves superseded generations, i.e. unreferenced
+/// generations created before the newest referenced one, out of `cfgmaps_orphans`. Unreferenced generations
+/// newer than any referenced one stay orphans. Generations are listed newest first.
+pub fn take_superseded_generations(
+    cfgmaps: &[ConfigMap],
+    cfgmaps_orphans: &mut HashSet<String>,
+) -> Vec<Generations> {
+    let mut by_base_name: BTreeMap<&str, Vec<&ConfigMap>> = BTreeMap::new();
+    for cfgmap in cfgmaps {
+        let base_name = cfgmap
+            .metadata
+            .name
+            .as_deref()
+            .and_then(|name| KUSTOMIZE_HASH_SUFFIX.captures(name))
+            .and_then(|captures| captures.get(1));
+        if let Some(base_name) = base_name {
+            by_base_name
+                .entry(base_name.as_str())
+                .or_default()
+                .push(cfgmap);
+        }
+    }
+
+    by_base_name
+        .into_iter()
+        .filter_map(|(base_name, mut generations)| {
+            let created =
+                |cfgmap: &ConfigMap| cfgmap.metadata.creation_timestamp.as_ref().map(|t| t.0);
+            generations.sort_by_key(|cfgmap| std::cmp::Reverse(created(cfgmap)));
+            let is_orphan = |cfgmap: &&ConfigMap| {
+                cfgmap
+                    .metadata
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| cfgmaps_orphans.contains(name))
+            };
+            let newest_referenced = generations
+                .iter()
+                .filter(|cfgmap| !is_orphan(cfgmap))
+                .filter_map(|cfgmap| created(cfgmap))
+                .max()?;
+
+            let referenced: Vec<String> = generations
+                .iter()
+                .filter(|cfgmap| !is_orphan(cfgmap))
+                .filter_map(|cfgmap| cfgmap.metadata.name.clone())
+                .collect();
+            let superseded: Vec<String> = generations
+                .iter()
+                .filter(|cfgmap| is_orphan(cfgmap))
+                .filter(|cfgmap| created(cfgmap).is_some_and(|time| time < newest_referenced))
+                .filter_map(|cfgmap| cfgmap.metadata.name.clone())
+                .collect();
+            if superseded.is_empty() {
+                return None;
+            }
+
+            superseded.iter().for_each(|name| {
+                cfgmaps_orphans.remove(name);
+            });
+            Some(Generations {
+                base_name: base_name.to_string(),
+                referenced,
+                superseded,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+    use k8s_openapi::chrono::{TimeZone, Utc};
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::kustomize::{take_superseded_generations, Generations};
+
+    fn cfgmap(name: &str, created_day: u32) -> ConfigMap {
+        ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                creation_timestamp: Some(Time(
+                    Utc.with_ymd_and_hms(2026, 1, created_day, 0, 0, 0).unwrap(),
+                )),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        }
+    }
+
+    #[test]
+    fn superseded_generations_grouped_by_base_name() {
+        let cfgmaps = vec![
+            cfgmap("app-config-7h2kdg5m6c", 2),
+            cfgmap("app-config-b9g8t4hmk2", 1),
+            cfgmap("old-config-7h2kdg5m6c", 1),
+            cfgmap("plain-config", 1),
+        ];
+        let mut cfgmaps_orphans = HashSet::from([
+            "app-config-b9g8t4hmk2".to_string(),
+            "old-config-7h2kdg5m6c".to_string(),
+            "plain-config".to_string(),
+        ]);
+
+        let generations = take_superseded_generations(&cfgmaps, &mut cfgmaps_orphans);
+
+        assert_eq!(
+            generations,
+            vec![Generations {
+                base_name: "app-config".to_string(),
+                referenced: vec!["app-config-7h2kdg5m6c".to_string()],
+                superseded: vec!["app-config-b9g8t4hmk2".to_string()],
+            }]
+        );
+        assert_eq!(
+            cfgmaps_orphans,
+            HashSet::from([
+                "old-config-7h2kdg5m6c".to_string(),
+                "plain-config".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn newer_unreferenced_generation_stays_orphan() {
+        let cfgmaps = vec![
+            cfgmap("app-config-7h2kdg5m6c", 3),
+            cfgmap("app-config-b9g8t4hmk2", 2),
+            cfgmap("app-config-m6c7h2kdg5", 1),
+        ];
+        let mut cfgmaps_orphans = HashSet::from([
+            "app-config-7h2kdg5m6c".to_string(),
+            "app-config-m6c7h2kdg5".to_string(),
+        ]);
+
+        let generations = take_superseded_generations(&cfgmaps, &mut cfgmaps_orphans);
+
+        assert_eq!(
+            generations,
+            vec![Generations {
+                base_name: "app-config".to_string(),
+                referenced: vec!["app-config-b9g8t4hmk2".to_string()],
+                superseded: vec!["app-config-m6c7h2kdg5".to_string()],
+            }]
+        );
+        assert_eq!(
+            cfgmaps_orphans,
+            HashSet::from(["app-config-7h2kdg5m6c".to_string()])
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use k8s_openapi::api::batch::v1::Job;
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+use k8s_openapi::chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::age::format_age;
+
+/// A finished `Job` or `Pod` that is never cleaned up automatically.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Leftover {
+    pub name: String,
+    pub phase: String,
+    pub finished_at: Option<Time>,
+    pub age: Option<String>,
+}
+
+/// Finds completed or failed `Job`s without `ttlSecondsAfterFinished` that are not owned by a `CronJob`.
+pub fn find_finished_jobs(jobs: &[Job], now: DateTime<Utc>) -> Vec<Leftover> {
+    let mut leftovers: Vec<Leftover> = jobs
+        .iter()
+        .filter(|job| {
+            job.spec
+                .as_ref()
+                .is_none_or(|spec| spec.ttl_seconds_after_finished.is_none())
+        })
+        .filter(|job| !is_owned_by(&job.metadata, |owner_kind, _| owner_kind == "CronJob"))
+        .filter_map(|job| {
+            let status = job.status.as_ref()?;
+            let finished_condition = status.conditions.iter().flatten().find(|condition| {
+                (condition.type_ == "Complete" || condition.type_ == "Failed")
+                    && condition.status == "True"
+            })?;
+            let finished_at = match finished_condition.type_.as_str() {
+                "Complete" => status.completion_time.clone(),
+                _ => None,
+            }
+            .or_else(|| finished_condition.last_transition_time.clone());
+
+            leftover(&job.metadata, &finished_condition.type_, finished_at, now)
+        })
+        .collect();
+    leftovers.sort_by(|a, b| a.name.cmp(&b.name));
+    leftovers
+}
+
+/// Finds `Pod`s in the `Succeeded` or `Failed` phase without a controller owning them.
+pub fn find_finished_pods(pods: &[Pod], now: DateTime<Utc>) -> Vec<Leftover> {
+    let mut leftovers: Vec<Leftover> = pods
+        .iter()
+        .filter(|pod| !is_owned_by(&pod.metadata, |_, controller| controller))
+        .filter_map(|pod| {
+            let status = pod.status.as_ref()?;
+            let phase = status
+                .phase
+                .as_ref()
+                .filter(|phase| *phase == "Succeeded" || *phase == "Failed")?;
+            let finished_at = status
+                .container_statuses
+                .iter()
+                .flatten()
+                .filter_map(|container_status| container_status.state.as_ref()?.terminated.as_ref())
+                .filter_map(|terminated| terminated.finished_at.clone())
+                .max_by_key(|finished_at| finished_at.0);
+
+            leftover(&pod.metadata, phase, finished_at, now)
+        })
+        .collect();
+    leftovers.sort_by(|a, b| a.name.cmp(&b.name));
+    leftovers
+}
+
+fn leftover(
+    metadata: &ObjectMeta,
+    phase: &str,
+    finished_at: Option<Time>,
+    now: DateTime<Utc>,
+) -> Option<Leftover> {
+    Some(Leftover {
+        name: metadata.name.clone()?,
+        phase: phase.to_string(),
+        finished_at,
+        age: metadata
+            .creation_timestamp
+            .as_ref()
+            .map(|created| format_age(created, now)),
+    })
+}
+
+/// Checks whether any owner reference satisfies the `predicate`, given the owner's kind
+/// and whether the owner is a controller.
+fn is_owned_by(metadata: &ObjectMeta, predicate: impl Fn(&str, bool) -> bool) -> bool {
+    metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .any(|owner| predicate(&owner.kind, owner.controller.unwrap_or(false)))
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::batch::v1::{Job, JobCondition, JobSpec, JobStatus};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{OwnerReference, Time};
+    use k8s_openapi::chrono::{Duration, Utc};
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::leftovers::{find_finished_jobs, Leftover};
+
+    fn finished_job(name: &str, owner_kind: Option<&str>, ttl: Option<i32>) -> Job {
+        Job {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                owner_references: owner_kind.map(|kind| {
+                    vec![OwnerReference {
+                        kind: kind.to_string(),
+                        controller: Some(true),
+                        ..OwnerReference::default()
+                    }]
+                }),
+                ..ObjectMeta::default()
+            },
+            spec: Some(JobSpec {
+                ttl_seconds_after_finished: ttl,
+                ..JobSpec::default()
+            }),
+            status: Some(JobStatus {
+                conditions: Some(vec![JobCondition {
+                    type_: "Failed".to_string(),
+                    status: "True".to_string(),
+                    ..JobCondition::default()
+                }]),
+                ..JobStatus::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn finished_jobs_without_ttl_or_cronjob() {
+        let now = Utc::now();
+        let mut leftover_job = finished_job("leftover", None, None);
+        leftover_job.metadata.creation_timestamp = Some(Time(now - Duration::days(3)));
+        let jobs = vec![
+            leftover_job,
+            finished_job("scheduled", Some("CronJob"), None),
+            finished_job("expiring", None, Some(60)),
+        ];
+
+        assert_eq!(
+            find_finished_jobs(&jobs, now),
+            vec![Leftover {
+                name: "leftover".to_string(),
+                phase: "Failed".to_string(),
+                finished_at: None,
+                age: Some("3d".to_string()),
+            }]
+        );
+    }
+}
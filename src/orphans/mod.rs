@@ -1,40 +1,165 @@
-use std::collections::HashSet;
-use std::sync::Mutex;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use anyhow::Result;
 use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
 use k8s_openapi::api::batch::v1::CronJob;
 use k8s_openapi::api::batch::v1::Job;
 use k8s_openapi::api::core::v1::{
-    ConfigMap, Pod, PodSpec, ReplicationController, Secret, ServiceAccount,
+    ConfigMap, Namespace, Pod, PodSpec, ReplicationController, Secret, ServiceAccount,
 };
-use k8s_openapi::api::networking::v1::Ingress;
+use k8s_openapi::api::networking::v1::{Ingress, NetworkPolicy};
+use k8s_openapi::api::policy::v1::PodDisruptionBudget;
+use k8s_openapi::api::rbac::v1::{ClusterRole, ClusterRoleBinding, Role, RoleBinding};
+use k8s_openapi::chrono::{DateTime, Duration, Utc};
+use kube::api::DynamicObject;
 use kube::Client;
 use rayon::prelude::*;
 use serde::Serialize;
 
+use crate::orphans::certificates::{find_tls_certificates, TlsCertificate};
+use crate::orphans::helm::{
+    extend_with_releases, find_helm_releases, is_release_secret, HelmRelease,
+};
+use crate::orphans::kustomize::{take_superseded_generations, Generations};
+use crate::orphans::leftovers::{find_finished_jobs, find_finished_pods, Leftover};
+use crate::orphans::network_policies::{
+    find_dangling_peers, find_orphan_network_policies, DanglingPeer,
+};
+use crate::orphans::rbac::{
+    find_dangling_cluster_role_bindings, find_dangling_role_bindings, find_unbound_roles,
+    DanglingBinding,
+};
+use crate::orphans::references::{
+    explain, extend_with_referrers, index_references, is_image_pull_secrets_path,
+    pod_spec_references, referrers_references, Explained, ObjectKind, ObjectRef, PodSpecReferrer,
+};
+use crate::orphans::replicasets::{find_stale_replicasets, StaleReplicaSets};
+use crate::orphans::scaling::{
+    extend_with_keys, find_orphan_hpas, find_orphan_pdbs, find_orphan_vpas, vpa_api_resource,
+    WorkloadKeys,
+};
+use crate::orphans::secret_types::{
+    gateway_api_resource, is_pull_secret, is_unexpired_bootstrap_token, matches_type, secret_type,
+    OPAQUE,
+};
+use crate::orphans::sizes::{
+    find_large_configmaps, find_large_secrets, sum_sizes, Footprint, Sizes,
+};
+use crate::orphans::tokens::{find_orphan_tokens, is_service_account_token, OrphanToken};
 use crate::pod_spec::ResourceWithPodSpec;
-use crate::resources::list_resource;
+use crate::resources::{
+    list_optional_cluster_resource, list_optional_namespaced_resource, list_optional_resource,
+    list_resource,
+};
+
+mod certificates;
+mod helm;
+mod kustomize;
+mod leftovers;
+mod network_policies;
+mod rbac;
+pub mod references;
+mod replicasets;
+pub mod scaling;
+mod secret_types;
+mod sizes;
+pub mod tokens;
+pub mod what_if;
+
+/// Created by Kubernetes with identical content in every namespace.
+pub(crate) const ROOT_CA_CERT: &str = "kube-root-ca.crt";
+
+/// Settings of the orphan detection.
+pub struct OrphanOptions {
+    /// Number of `ReplicaSet` revisions scaled to zero kept per `Deployment` for rollbacks.
+    pub revision_history: usize,
+    /// Types of `Secret`s to report, e.g. `kubernetes.io/tls` or just `tls`. All types when empty.
+    pub secret_types: Vec<String>,
+    /// Number of Helm release revisions kept, including the current one.
+    pub helm_history: usize,
+    /// Percentage of the size limit from which `ConfigMap`s and `Secret`s are reported as large.
+    pub size_threshold: u8,
+    /// Number of days before expiry from which TLS certificates are reported as expiring.
+    pub expiry_warning: i64,
+    /// Whether to list references keeping every used `ConfigMap` and `Secret` alive.
+    pub explain: bool,
+}
+
+impl Default for OrphanOptions {
+    fn default() -> Self {
+        OrphanOptions {
+            revision_history: 3,
+            secret_types: Vec::new(),
+            helm_history: 10,
+            size_threshold: 50,
+            expiry_warning: 30,
+            explain: false,
+        }
+    }
+}
+
+pub async fn find_orphans(
+    client: &Client,
+    namespace: &str,
+    options: &OrphanOptions,
+) -> Result<Orphans> {
+    let resources = list_namespace_resources(client, namespace).await?;
+    Ok(detect_orphans(namespace, &resources, options, Utc::now()))
+}
+
+/// Resources of a namespace inspected by the orphan detection.
+#[derive(Default)]
+pub(crate) struct NamespaceResources {
+    pub(crate) cfgmaps: Vec<ConfigMap>,
+    pub(crate) secrets: Vec<Secret>,
+    deployments: Vec<Deployment>,
+    replicasets: Vec<ReplicaSet>,
+    statefulsets: Vec<StatefulSet>,
+    daemonsets: Vec<DaemonSet>,
+    jobs: Vec<Job>,
+    cronjobs: Vec<CronJob>,
+    replication_controllers: Vec<ReplicationController>,
+    pods: Vec<Pod>,
+    ingresses: Vec<Ingress>,
+    service_accounts: Vec<ServiceAccount>,
+    hpas: Option<Vec<HorizontalPodAutoscaler>>,
+    vpas: Option<Vec<DynamicObject>>,
+    pdbs: Option<Vec<PodDisruptionBudget>>,
+    network_policies: Option<Vec<NetworkPolicy>>,
+    roles: Option<Vec<Role>>,
+    role_bindings: Option<Vec<RoleBinding>>,
+    cluster_roles: Option<Vec<ClusterRole>>,
+    cluster_role_bindings: Option<Vec<ClusterRoleBinding>>,
+    gateways: Option<Vec<DynamicObject>>,
+    namespaces: Option<Vec<Namespace>>,
+}
 
-const ROOT_CA_CERT: &str = "kube-root-ca.crt";
+impl NamespaceResources {
+    fn pod_spec_referrers(&self) -> Vec<PodSpecReferrer<'_>> {
+        let mut pod_spec_referrers = Vec::new();
+        extend_with_referrers(&mut pod_spec_referrers, &self.deployments);
+        extend_with_referrers(&mut pod_spec_referrers, &self.replicasets);
+        extend_with_referrers(&mut pod_spec_referrers, &self.statefulsets);
+        extend_with_referrers(&mut pod_spec_referrers, &self.daemonsets);
+        extend_with_referrers(&mut pod_spec_referrers, &self.jobs);
+        extend_with_referrers(&mut pod_spec_referrers, &self.cronjobs);
+        extend_with_referrers(&mut pod_spec_referrers, &self.replication_controllers);
+        extend_with_referrers(&mut pod_spec_referrers, &self.pods);
+        pod_spec_referrers
+    }
+}
 
-pub async fn find_orphans(client: &Client, namespace: &str) -> Result<Orphans> {
+pub(crate) async fn list_namespace_resources(
+    client: &Client,
+    namespace: &str,
+) -> Result<NamespaceResources> {
     let configmaps_fut = list_resource::<ConfigMap>(client, namespace);
     let secrets_fut = list_resource::<Secret>(client, namespace);
     let (cfgmaps, secrets) = tokio::try_join!(configmaps_fut, secrets_fut)?;
 
-    // Move names of configmaps and secrets into HashSets. Later, remove any configmap's or secret's
-    // name that's being referenced to. The resulting HashSet only contains unreferenced elements.
-    let mut cfgmaps_orphans: HashSet<String> = cfgmaps
-        .into_iter()
-        .filter_map(|r| r.metadata.name)
-        .collect();
-
-    let mut secrets_orphans: HashSet<String> = secrets
-        .into_iter()
-        .filter_map(|r| r.metadata.name)
-        .collect();
-
+    let vpa_resource = vpa_api_resource();
+    let gateway_resource = gateway_api_resource();
     // Kubernetes API Denial Of Service attack :)
     let (
         deployments,
@@ -47,6 +172,15 @@ pub async fn find_orphans(client: &Client, namespace: &str) -> Result<Orphans> {
         pods,
         ingresses,
         service_accounts,
+        hpas,
+        vpas,
+        pdbs,
+        network_policies,
+        roles,
+        role_bindings,
+        cluster_roles,
+        cluster_role_bindings,
+        gateways,
     ) = tokio::try_join!(
         list_resource::<Deployment>(client, namespace),
         list_resource::<ReplicaSet>(client, namespace),
@@ -57,113 +191,468 @@ pub async fn find_orphans(client: &Client, namespace: &str) -> Result<Orphans> {
         list_resource::<ReplicationController>(client, namespace),
         list_resource::<Pod>(client, namespace),
         list_resource::<Ingress>(client, namespace),
-        list_resource::<ServiceAccount>(client, namespace)
+        list_resource::<ServiceAccount>(client, namespace),
+        list_optional_namespaced_resource::<HorizontalPodAutoscaler>(client, namespace),
+        list_optional_resource(client, namespace, &vpa_resource),
+        list_optional_namespaced_resource::<PodDisruptionBudget>(client, namespace),
+        list_optional_namespaced_resource::<NetworkPolicy>(client, namespace),
+        list_optional_namespaced_resource::<Role>(client, namespace),
+        list_optional_namespaced_resource::<RoleBinding>(client, namespace),
+        list_optional_cluster_resource::<ClusterRole>(client),
+        list_optional_cluster_resource::<ClusterRoleBinding>(client),
+        list_optional_resource(client, namespace, &gateway_resource)
     )?;
+    // Namespaces are cluster-scoped, fetched separately not to fail users with namespaced RBAC only.
+    let namespaces = list_optional_cluster_resource::<Namespace>(client).await?;
+
+    Ok(NamespaceResources {
+        cfgmaps,
+        secrets,
+        deployments,
+        replicasets,
+        statefulsets,
+        daemonsets,
+        jobs,
+        cronjobs,
+        replication_controllers,
+        pods,
+        ingresses,
+        service_accounts,
+        hpas,
+        vpas,
+        pdbs,
+        network_policies,
+        roles,
+        role_bindings,
+        cluster_roles,
+        cluster_role_bindings,
+        gateways,
+        namespaces,
+    })
+}
+
+/// Runs the orphan detection over already listed `resources` of the `namespace`.
+fn detect_orphans(
+    namespace: &str,
+    resources: &NamespaceResources,
+    options: &OrphanOptions,
+    now: DateTime<Utc>,
+) -> Orphans {
+    let NamespaceResources {
+        cfgmaps,
+        secrets,
+        deployments,
+        replicasets,
+        statefulsets,
+        daemonsets,
+        jobs,
+        cronjobs,
+        replication_controllers,
+        pods,
+        ingresses,
+        service_accounts,
+        hpas,
+        vpas,
+        pdbs,
+        network_policies,
+        roles,
+        role_bindings,
+        cluster_roles,
+        cluster_role_bindings,
+        gateways,
+        namespaces,
+    } = resources;
+    let (cfgmaps_names, secrets_names, secret_types) = orphan_candidates(cfgmaps, secrets, now);
+
     let mut pod_specs: Vec<&PodSpec> = Vec::new();
 
-    extend_with(&mut pod_specs, &deployments);
-    extend_with(&mut pod_specs, &replicasets);
-    extend_with(&mut pod_specs, &statefulsets);
-    extend_with(&mut pod_specs, &daemonsets);
-    extend_with(&mut pod_specs, &jobs);
-    extend_with(&mut pod_specs, &cronjobs);
-    extend_with(&mut pod_specs, &replication_controllers);
-    extend_with(&mut pod_specs, &pods);
-
-    let locked_secret_orphans: Mutex<&mut HashSet<String>> = Mutex::new(&mut secrets_orphans);
-    let locked_configmap_orphans: Mutex<&mut HashSet<String>> = Mutex::new(&mut cfgmaps_orphans);
-    pod_specs.par_iter().for_each(|pod_spec| {
-        find_references_in_podspec(pod_spec, &locked_secret_orphans, &locked_configmap_orphans)
+    extend_with(&mut pod_specs, deployments);
+    extend_with(&mut pod_specs, statefulsets);
+    extend_with(&mut pod_specs, daemonsets);
+    extend_with(&mut pod_specs, jobs);
+    extend_with(&mut pod_specs, cronjobs);
+    extend_with(&mut pod_specs, replication_controllers);
+    extend_with(&mut pod_specs, pods);
+
+    // Pod specs of stale ReplicaSets are inspected separately, to find out which configmaps
+    // and secrets would become orphans once these ReplicaSets are removed.
+    let stale_revisions =
+        find_stale_replicasets(replicasets, deployments, options.revision_history);
+    let stale_replicasets: HashSet<&str> = stale_revisions
+        .iter()
+        .flat_map(|revisions| revisions.replicasets.iter().map(String::as_str))
+        .collect();
+    let mut pod_specs_without_stale = pod_specs.clone();
+    pod_specs_without_stale.extend(
+        replicasets
+            .iter()
+            .filter(|rs| {
+                rs.metadata
+                    .name
+                    .as_deref()
+                    .is_none_or(|name| !stale_replicasets.contains(name))
+            })
+            .filter_map(|rs| rs.pod_template_spec()),
+    );
+    extend_with(&mut pod_specs, replicasets);
+
+    let mut pod_labels: Vec<Option<&BTreeMap<String, String>>> = Vec::new();
+    extend_with_labels(&mut pod_labels, deployments);
+    extend_with_labels(&mut pod_labels, replicasets);
+    extend_with_labels(&mut pod_labels, statefulsets);
+    extend_with_labels(&mut pod_labels, daemonsets);
+    extend_with_labels(&mut pod_labels, jobs);
+    extend_with_labels(&mut pod_labels, cronjobs);
+    extend_with_labels(&mut pod_labels, replication_controllers);
+    extend_with_labels(&mut pod_labels, pods);
+
+    let referrers = Referrers {
+        namespace,
+        ingresses,
+        gateways: gateways.as_deref().unwrap_or_default(),
+        service_accounts,
+    };
+    let mut cfgmaps_orphans = cfgmaps_names.clone();
+    let mut secrets_orphans = secrets_names.clone();
+    remove_referenced(
+        &mut cfgmaps_orphans,
+        &mut secrets_orphans,
+        &secret_types,
+        &pod_specs,
+        &referrers,
+    );
+
+    let mut cfgmaps_orphans_without_stale = cfgmaps_names;
+    let mut secrets_orphans_without_stale = secrets_names;
+    remove_referenced(
+        &mut cfgmaps_orphans_without_stale,
+        &mut secrets_orphans_without_stale,
+        &secret_types,
+        &pod_specs_without_stale,
+        &referrers,
+    );
+    cfgmaps_orphans_without_stale.retain(|cfgmap| !cfgmaps_orphans.contains(cfgmap));
+    secrets_orphans_without_stale.retain(|secret| !secrets_orphans.contains(secret));
+
+    let superseded_cfgmaps = take_superseded_generations(cfgmaps, &mut cfgmaps_orphans);
+
+    let mut workload_keys = WorkloadKeys::new();
+    extend_with_keys(&mut workload_keys, deployments);
+    extend_with_keys(&mut workload_keys, replicasets);
+    extend_with_keys(&mut workload_keys, statefulsets);
+    extend_with_keys(&mut workload_keys, daemonsets);
+    extend_with_keys(&mut workload_keys, jobs);
+    extend_with_keys(&mut workload_keys, cronjobs);
+    extend_with_keys(&mut workload_keys, replication_controllers);
+
+    let mut releases_with_resources: HashSet<&str> = HashSet::new();
+    extend_with_releases(&mut releases_with_resources, deployments);
+    extend_with_releases(&mut releases_with_resources, statefulsets);
+    extend_with_releases(&mut releases_with_resources, daemonsets);
+    extend_with_releases(&mut releases_with_resources, jobs);
+    extend_with_releases(&mut releases_with_resources, cronjobs);
+    extend_with_releases(&mut releases_with_resources, replication_controllers);
+    extend_with_releases(&mut releases_with_resources, ingresses);
+    extend_with_releases(&mut releases_with_resources, service_accounts);
+    extend_with_releases(&mut releases_with_resources, cfgmaps);
+    extend_with_releases(&mut releases_with_resources, secrets);
+    extend_with_releases(
+        &mut releases_with_resources,
+        hpas.as_deref().unwrap_or_default(),
+    );
+    extend_with_releases(
+        &mut releases_with_resources,
+        pdbs.as_deref().unwrap_or_default(),
+    );
+    extend_with_releases(
+        &mut releases_with_resources,
+        network_policies.as_deref().unwrap_or_default(),
+    );
+    extend_with_releases(
+        &mut releases_with_resources,
+        roles.as_deref().unwrap_or_default(),
+    );
+    extend_with_releases(
+        &mut releases_with_resources,
+        role_bindings.as_deref().unwrap_or_default(),
+    );
+
+    let with_types = |secrets: HashSet<String>| -> HashMap<String, String> {
+        secrets
+            .into_iter()
+            .map(|secret| {
+                let secret_type = secret_types.get(secret.as_str()).copied();
+                (secret, secret_type.unwrap_or(OPAQUE).to_string())
+            })
+            .filter(|(_, secret_type)| {
+                options.secret_types.is_empty()
+                    || options
+                        .secret_types
+                        .iter()
+                        .any(|filter| matches_type(secret_type, filter))
+            })
+            .collect()
+    };
+
+    let tls_certificates = find_tls_certificates(
+        secrets,
+        &secrets_orphans,
+        Duration::days(options.expiry_warning),
+        now,
+    );
+    let service_account_tokens = find_orphan_tokens(secrets, service_accounts);
+    let explained = options.explain.then(|| {
+        let index = index_references(&resources.pod_spec_referrers(), &referrers);
+
+        let used_cfgmaps = cfgmaps
+            .iter()
+            .filter_map(|cfgmap| cfgmap.metadata.name.as_deref())
+            .filter(|cfgmap| {
+                !cfgmaps_orphans.contains(*cfgmap)
+                    && !superseded_cfgmaps
+                        .iter()
+                        .any(|generations| generations.superseded.iter().any(|s| s == cfgmap))
+            })
+            .map(|cfgmap| (cfgmap, configmap_rule(cfgmap)));
+        let used_secrets = secrets
+            .iter()
+            .filter(|secret| {
+                options.secret_types.is_empty()
+                    || options
+                        .secret_types
+                        .iter()
+                        .any(|filter| matches_type(secret_type(secret), filter))
+            })
+            .filter_map(|secret| {
+                let name = secret.metadata.name.as_deref()?;
+                Some((name, secret_rule(secret, &service_account_tokens, now)))
+            })
+            .filter(|(name, _)| {
+                !secrets_orphans.contains(*name)
+                    && !service_account_tokens
+                        .iter()
+                        .any(|token| token.name == *name)
+            });
+
+        Explained {
+            configmaps: explain(ObjectKind::ConfigMap, used_cfgmaps, &index),
+            secrets: explain(ObjectKind::Secret, used_secrets, &index),
+        }
     });
+    let secrets_orphans = with_types(secrets_orphans);
+    let helm_releases = find_helm_releases(secrets, &releases_with_resources, options.helm_history);
 
-    ingresses
+    let orphan_cfgmap_names = orphan_configmap_names(&cfgmaps_orphans, &superseded_cfgmaps);
+    let orphan_secret_names =
+        orphan_secret_names(&secrets_orphans, &service_account_tokens, &helm_releases);
+    let cfgmaps_footprint = sum_sizes(
+        cfgmaps,
+        |cfgmap| cfgmap.metadata.name.as_deref(),
+        &orphan_cfgmap_names,
+    );
+    let secrets_footprint = sum_sizes(
+        secrets,
+        |secret| secret.metadata.name.as_deref(),
+        &orphan_secret_names,
+    );
+    let sizes = Sizes {
+        configmaps: find_large_configmaps(cfgmaps, options.size_threshold),
+        secrets: find_large_secrets(secrets, options.size_threshold),
+        orphans_footprint: Footprint {
+            configmaps: cfgmaps_footprint,
+            secrets: secrets_footprint,
+            total: cfgmaps_footprint + secrets_footprint,
+        },
+    };
+
+    Orphans {
+        configmaps: cfgmaps_orphans,
+        superseded_configmaps: superseded_cfgmaps,
+        secrets: secrets_orphans,
+        service_account_tokens,
+        helm_releases,
+        tls_certificates,
+        horizontal_pod_autoscalers: hpas
+            .as_deref()
+            .map(|hpas| find_orphan_hpas(hpas, &workload_keys)),
+        vertical_pod_autoscalers: vpas
+            .as_deref()
+            .map(|vpas| find_orphan_vpas(vpas, &workload_keys)),
+        pod_disruption_budgets: pdbs.as_deref().map(|pdbs| find_orphan_pdbs(pdbs, pods)),
+        network_policies: network_policies
+            .as_deref()
+            .map(|network_policies| find_orphan_network_policies(network_policies, &pod_labels)),
+        network_policy_peers: network_policies.as_deref().zip(namespaces.as_deref()).map(
+            |(network_policies, namespaces)| find_dangling_peers(network_policies, namespaces),
+        ),
+        role_bindings: role_bindings.as_deref().zip(roles.as_deref()).map(
+            |(role_bindings, roles)| {
+                find_dangling_role_bindings(
+                    namespace,
+                    role_bindings,
+                    roles,
+                    cluster_roles.as_deref(),
+                    service_accounts,
+                )
+            },
+        ),
+        cluster_role_bindings: cluster_role_bindings.as_ref().map(|cluster_role_bindings| {
+            find_dangling_cluster_role_bindings(
+                namespace,
+                cluster_role_bindings,
+                cluster_roles.as_deref(),
+                service_accounts,
+            )
+        }),
+        roles: roles
+            .as_deref()
+            .zip(role_bindings.as_deref())
+            .map(|(roles, role_bindings)| find_unbound_roles(roles, role_bindings)),
+        jobs: find_finished_jobs(jobs, now),
+        pods: find_finished_pods(pods, now),
+        replicasets: StaleReplicaSets {
+            deployments: stale_revisions,
+            configmaps: cfgmaps_orphans_without_stale,
+            secrets: with_types(secrets_orphans_without_stale),
+        },
+        sizes,
+        explained,
+    }
+}
+
+/// Names of all orphan `ConfigMap`s, including superseded generations.
+fn orphan_configmap_names<'a>(
+    cfgmaps_orphans: &'a HashSet<String>,
+    superseded_cfgmaps: &'a [Generations],
+) -> HashSet<&'a str> {
+    cfgmaps_orphans
         .iter()
-        .filter_map(|ingress| ingress.spec.as_ref())
-        .filter_map(|a| a.tls.as_ref())
-        .flatten()
-        .filter_map(|tls| tls.secret_name.as_ref())
-        .for_each(|secret| {
-            secrets_orphans.remove(secret);
-        });
-
-    service_accounts
+        .chain(
+            superseded_cfgmaps
+                .iter()
+                .flat_map(|generations| &generations.superseded),
+        )
+        .map(String::as_str)
+        .collect()
+}
+
+/// Names of all orphan `Secret`s, including service account tokens and stale Helm release revisions.
+fn orphan_secret_names<'a>(
+    secrets_orphans: &'a HashMap<String, String>,
+    service_account_tokens: &'a [OrphanToken],
+    helm_releases: &'a [HelmRelease],
+) -> HashSet<&'a str> {
+    secrets_orphans
+        .keys()
+        .chain(service_account_tokens.iter().map(|token| &token.name))
+        .chain(
+            helm_releases
+                .iter()
+                .flat_map(|release| &release.stale_revisions),
+        )
+        .map(String::as_str)
+        .collect()
+}
+
+/// Names of `ConfigMap`s and `Secret`s that may be orphans, with types of all `Secret`s. Later, any
+/// referenced name is removed, leaving only unreferenced ones. Helm release `Secret`s and unexpired
+/// bootstrap tokens are never orphans. Service account tokens are checked against their `ServiceAccount`
+/// separately.
+fn orphan_candidates<'a>(
+    cfgmaps: &[ConfigMap],
+    secrets: &'a [Secret],
+    now: DateTime<Utc>,
+) -> (HashSet<String>, HashSet<String>, HashMap<&'a str, &'a str>) {
+    let cfgmaps_names: HashSet<String> = cfgmaps
         .iter()
-        .filter_map(|sa| sa.image_pull_secrets.as_ref())
-        .flatten()
-        .map(|secret| secret.name.clone())
-        .for_each(|secret| {
-            secrets_orphans.remove(&secret);
-        });
-
-    service_accounts
+        .filter_map(|r| r.metadata.name.clone())
+        .collect();
+    let secrets_names: HashSet<String> = secrets
         .iter()
-        .filter_map(|sa| sa.secrets.as_ref())
-        .flatten()
-        .filter_map(|secret| secret.name.as_ref())
-        .for_each(|secret| {
-            secrets_orphans.remove(secret);
-        });
+        .filter(|r| {
+            !is_unexpired_bootstrap_token(r, now)
+                && !is_release_secret(r)
+                && !is_service_account_token(r)
+        })
+        .filter_map(|r| r.metadata.name.clone())
+        .collect();
+    let secret_types: HashMap<&str, &str> = secrets
+        .iter()
+        .filter_map(|r| Some((r.metadata.name.as_deref()?, secret_type(r))))
+        .collect();
+    (cfgmaps_names, secrets_names, secret_types)
+}
 
-    cfgmaps_orphans.remove(ROOT_CA_CERT);
-    Ok(Orphans::new(cfgmaps_orphans, secrets_orphans))
+/// The rule keeping the `ConfigMap` in use without being referenced, if any.
+pub(super) fn configmap_rule(name: &str) -> Option<&'static str> {
+    (name == ROOT_CA_CERT).then_some("root CA certificate")
 }
 
-/// Inspects given `pod_spec` for references on `ConfigMap`s and `Secret`s.
-/// If any reference is found, it is removed from the list of existing configmaps or secrets respectively.
-fn find_references_in_podspec(
-    pod_spec: &PodSpec,
-    locked_secret_orphans: &Mutex<&mut HashSet<String>>,
-    locked_configmap_orphans: &Mutex<&mut HashSet<String>>,
-) {
-    pod_spec
-        .containers
-        .iter()
-        .map(|container| (&container.env_from, &container.env))
-        .for_each(|(envs_from_source, env_vars)| {
-            if let Some(envs) = &envs_from_source {
-                envs.iter().for_each(|env_from_source| {
-                    if let Some(cfgmap) = env_from_source.config_map_ref.as_ref() {
-                        let mut locked_cfg_maps = locked_configmap_orphans.lock().unwrap();
-                        locked_cfg_maps.remove(&cfgmap.name);
-                    }
-
-                    if let Some(secret) = env_from_source.secret_ref.as_ref() {
-                        let mut locked_secrets = locked_secret_orphans.lock().unwrap();
-                        locked_secrets.remove(&secret.name);
-                    }
-                });
-            }
+/// The rule keeping the `Secret` in use without being referenced, if any. Service account tokens
+/// are in use unless among the `orphan_tokens`.
+pub(super) fn secret_rule(
+    secret: &Secret,
+    orphan_tokens: &[OrphanToken],
+    now: DateTime<Utc>,
+) -> Option<&'static str> {
+    if is_release_secret(secret) {
+        Some("Helm release")
+    } else if is_unexpired_bootstrap_token(secret, now) {
+        Some("unexpired bootstrap token")
+    } else if is_service_account_token(secret)
+        && !orphan_tokens
+            .iter()
+            .any(|token| Some(&token.name) == secret.metadata.name.as_ref())
+    {
+        Some("service account token")
+    } else {
+        None
+    }
+}
 
-            if let Some(envs) = &env_vars {
-                envs.iter()
-                    .filter_map(|env_var| env_var.value_from.as_ref())
-                    .for_each(|env_var_source| {
-                        if let Some(cfgmap) = &env_var_source.config_map_key_ref {
-                            let mut locked_cfg_maps = locked_configmap_orphans.lock().unwrap();
-                            locked_cfg_maps.remove(&cfgmap.name);
-                        }
-
-                        if let Some(secret) = &env_var_source.secret_key_ref {
-                            let mut locked_secrets = locked_secret_orphans.lock().unwrap();
-                            locked_secrets.remove(&secret.name);
-                        }
-                    });
-            }
-        });
+/// Resources other than pod specs referencing `ConfigMap`s and `Secret`s.
+struct Referrers<'a> {
+    namespace: &'a str,
+    ingresses: &'a [Ingress],
+    gateways: &'a [DynamicObject],
+    service_accounts: &'a [ServiceAccount],
+}
 
-    if let Some(volumes) = &pod_spec.volumes {
-        volumes.iter().for_each(|volume| {
-            if let Some(cfgmap) = volume.config_map.as_ref() {
-                let mut locked_cfgmaps = locked_configmap_orphans.lock().unwrap();
-                locked_cfgmaps.remove(&cfgmap.name);
-            }
+/// Removes any `ConfigMap` and `Secret` referenced by given `pod_specs` or other `referrers`
+/// from the orphan candidates. Docker config `Secret`s only count as referenced by `imagePullSecrets`.
+/// The root CA certificate is never considered an orphan.
+fn remove_referenced(
+    cfgmaps_orphans: &mut HashSet<String>,
+    secrets_orphans: &mut HashSet<String>,
+    secret_types: &HashMap<&str, &str>,
+    pod_specs: &[&PodSpec],
+    referrers: &Referrers,
+) {
+    let pod_spec_refs: Vec<(ObjectRef, String)> = pod_specs
+        .par_iter()
+        .flat_map_iter(|pod_spec| pod_spec_references(pod_spec, "spec"))
+        .map(|reference| (reference.object, reference.path))
+        .collect();
+    let other_refs = referrers_references(referrers)
+        .into_iter()
+        .map(|(_, _, object, path)| (object, path));
 
-            if let Some(secret) = volume.secret.as_ref() {
-                let mut lock_secrets = locked_secret_orphans.lock().unwrap();
-                lock_secrets.remove(secret.secret_name.as_ref().unwrap());
+    for (object, path) in pod_spec_refs.into_iter().chain(other_refs) {
+        match object.kind {
+            ObjectKind::ConfigMap => {
+                cfgmaps_orphans.remove(&object.name);
             }
-        });
+            ObjectKind::Secret => {
+                let is_pull_secret = secret_types
+                    .get(object.name.as_str())
+                    .is_some_and(|secret_type| is_pull_secret(secret_type));
+                if !is_pull_secret || is_image_pull_secrets_path(&path) {
+                    secrets_orphans.remove(&object.name);
+                }
+            }
+        }
     }
+    cfgmaps_orphans.remove(ROOT_CA_CERT);
 }
 
 pub fn extend_with<'a, T>(pod_specs: &mut Vec<&'a PodSpec>, extensions: &'a [T])
@@ -174,40 +663,118 @@ where
     pod_specs.extend(ext_pod_specs);
 }
 
+/// Collects pod template labels of resources with a pod spec. Resources without labels are
+/// included as `None`, as they are still matched by an empty selector.
+pub fn extend_with_labels<'a, T>(
+    pod_labels: &mut Vec<Option<&'a BTreeMap<String, String>>>,
+    extensions: &'a [T],
+) where
+    T: ResourceWithPodSpec,
+{
+    let ext_pod_labels = extensions
+        .iter()
+        .filter(|e| e.pod_template_spec().is_some())
+        .map(|e| e.pod_template_labels());
+    pod_labels.extend(ext_pod_labels);
+}
+
 #[derive(Serialize)]
 pub struct Orphans {
     pub configmaps: HashSet<String>,
-    pub secrets: HashSet<String>,
+    /// Kustomize-generated `ConfigMap`s superseded by a newer, referenced generation.
+    pub superseded_configmaps: Vec<Generations>,
+    /// Names of orphan `Secret`s with their type.
+    pub secrets: HashMap<String, String>,
+    pub service_account_tokens: Vec<OrphanToken>,
+    pub helm_releases: Vec<HelmRelease>,
+    /// Leaf certificates of all `kubernetes.io/tls` `Secret`s, with their expiry status.
+    pub tls_certificates: Vec<TlsCertificate>,
+    /// Orphan `HorizontalPodAutoscaler`s, unless they can't be listed.
+    #[serde(
+        rename = "horizontalpodautoscalers",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub horizontal_pod_autoscalers: Option<HashSet<String>>,
+    /// Orphan `VerticalPodAutoscaler`s, unless they can't be listed or are not installed.
+    #[serde(
+        rename = "verticalpodautoscalers",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub vertical_pod_autoscalers: Option<HashSet<String>>,
+    /// Orphan `PodDisruptionBudget`s, unless they can't be listed.
+    #[serde(
+        rename = "poddisruptionbudgets",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub pod_disruption_budgets: Option<HashSet<String>>,
+    /// Orphan `NetworkPolicy`s, unless they can't be listed.
+    #[serde(rename = "networkpolicies", skip_serializing_if = "Option::is_none")]
+    pub network_policies: Option<HashSet<String>>,
+    /// Peers selecting no namespace, unless `NetworkPolicy`s or `Namespace`s can't be listed.
+    #[serde(rename = "networkpolicypeers", skip_serializing_if = "Option::is_none")]
+    pub network_policy_peers: Option<Vec<DanglingPeer>>,
+    /// Dangling `RoleBinding`s, unless `Role`s or `RoleBinding`s can't be listed.
+    #[serde(rename = "rolebindings", skip_serializing_if = "Option::is_none")]
+    pub role_bindings: Option<Vec<DanglingBinding>>,
+    /// Dangling `ClusterRoleBinding`s, unless they can't be listed.
+    #[serde(
+        rename = "clusterrolebindings",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cluster_role_bindings: Option<Vec<DanglingBinding>>,
+    /// Unbound `Role`s, unless `Role`s or `RoleBinding`s can't be listed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roles: Option<HashSet<String>>,
+    pub jobs: Vec<Leftover>,
+    pub pods: Vec<Leftover>,
+    pub replicasets: StaleReplicaSets,
+    /// Large `ConfigMap`s and `Secret`s and the size of all orphan ones, in bytes.
+    pub sizes: Sizes,
+    /// References keeping used `ConfigMap`s and `Secret`s alive, with `--explain` only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explained: Option<Explained>,
 }
 
 impl Orphans {
-    pub fn new(configmaps: HashSet<String>, secrets: HashSet<String>) -> Self {
-        Orphans {
-            configmaps,
-            secrets,
-        }
+    fn configmap_names(&self) -> HashSet<&str> {
+        orphan_configmap_names(&self.configmaps, &self.superseded_configmaps)
+    }
+
+    fn secret_names(&self) -> HashSet<&str> {
+        orphan_secret_names(
+            &self.secrets,
+            &self.service_account_tokens,
+            &self.helm_releases,
+        )
     }
 }
 
 #[cfg(test)]
-mod test {
-    use std::collections::BTreeMap;
+mod tests {
+    use std::collections::{BTreeMap, HashMap, HashSet};
     use std::iter::FromIterator;
 
     use base64::engine::general_purpose;
     use base64::Engine;
     use k8s_openapi::api::apps::v1::{Deployment, DeploymentSpec};
     use k8s_openapi::api::core::v1::{
-        ConfigMap, ConfigMapEnvSource, Container, EnvFromSource, PodSpec, PodTemplateSpec, Secret,
-        SecretEnvSource,
+        ConfigMap, ConfigMapEnvSource, ConfigMapProjection, ConfigMapVolumeSource, Container,
+        EnvFromSource, LocalObjectReference, Pod, PodSpec, PodTemplateSpec, ProjectedVolumeSource,
+        Secret, SecretEnvSource, SecretVolumeSource, ServiceAccount, Volume, VolumeProjection,
     };
     use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+    use k8s_openapi::chrono::Utc;
     use k8s_openapi::serde::__private::TryFrom;
     use k8s_openapi::ByteString;
     use kube::api::{DeleteParams, ObjectMeta, PostParams};
     use kube::{Api, Client, Config, ResourceExt};
 
-    use crate::orphans::find_orphans;
+    use crate::orphans::sizes::{encoded_size, Footprint};
+    use crate::orphans::tokens::find_orphan_tokens;
+    use crate::orphans::{
+        configmap_rule, detect_orphans, find_orphans, orphan_candidates, remove_referenced,
+        secret_rule, NamespaceResources, OrphanOptions, Referrers,
+    };
 
     #[tokio::test]
     async fn cfgmap_secret_referenced_by_deployment() {
@@ -295,7 +862,6 @@ mod test {
                         }],
                         ..PodSpec::default()
                     }),
-                    ..PodTemplateSpec::default()
                 },
                 selector: LabelSelector {
                     match_labels: Some(BTreeMap::<String, String>::from_iter([(
@@ -318,12 +884,16 @@ mod test {
         let cfgmap_name = cfgmap.name_any();
         let secret_name = secret.name_any();
         // Both the ConfigMap and the Secret should not be detected as orphans.
-        let orphans = find_orphans(&client, &config.default_namespace)
-            .await
-            .expect("Orphans not returned.");
+        let orphans = find_orphans(
+            &client,
+            &config.default_namespace,
+            &OrphanOptions::default(),
+        )
+        .await
+        .expect("Orphans not returned.");
 
         assert!(!orphans.configmaps.contains(cfgmap_name.as_str()));
-        assert!(!orphans.secrets.contains(secret_name.as_str()));
+        assert!(!orphans.secrets.contains_key(secret_name.as_str()));
 
         // Free resources after the test
         dep_api
@@ -388,11 +958,15 @@ mod test {
         let cfgmap_name = cfgmap.name_any();
         let secret_name = secret.name_any();
         // Both the ConfigMap and the Secret should not be detected as orphans.
-        let orphans = find_orphans(&client, &config.default_namespace)
-            .await
-            .expect("Orphans not returned.");
+        let orphans = find_orphans(
+            &client,
+            &config.default_namespace,
+            &OrphanOptions::default(),
+        )
+        .await
+        .expect("Orphans not returned.");
         assert!(orphans.configmaps.contains(cfgmap_name.as_str()));
-        assert!(orphans.secrets.contains(secret_name.as_str()));
+        assert!(orphans.secrets.contains_key(secret_name.as_str()));
 
         // Free resources after the test
         secret_api
@@ -474,7 +1048,6 @@ mod test {
                         }],
                         ..PodSpec::default()
                     }),
-                    ..PodTemplateSpec::default()
                 },
                 selector: LabelSelector {
                     match_labels: Some(BTreeMap::<String, String>::from_iter([(
@@ -497,12 +1070,16 @@ mod test {
         let cfgmap_name = cfgmap.name_any();
         let secret_name = secret.name_any();
         // Both the ConfigMap and the Secret should not be detected as orphans.
-        let orphans = find_orphans(&client, &config.default_namespace)
-            .await
-            .expect("Orphans not returned.");
+        let orphans = find_orphans(
+            &client,
+            &config.default_namespace,
+            &OrphanOptions::default(),
+        )
+        .await
+        .expect("Orphans not returned.");
 
         assert!(orphans.configmaps.contains(cfgmap_name.as_str()));
-        assert!(orphans.secrets.contains(secret_name.as_str()));
+        assert!(orphans.secrets.contains_key(secret_name.as_str()));
 
         // Free resources after the test
         dep_api
@@ -518,4 +1095,218 @@ mod test {
             .await
             .expect("ConfigMap not deleted.");
     }
+
+    #[test]
+    fn pull_secret_referenced_by_image_pull_secrets_only() {
+        let pod_spec = PodSpec {
+            containers: vec![Container {
+                name: "nginx".to_string(),
+                env_from: Some(vec![EnvFromSource {
+                    secret_ref: Some(SecretEnvSource {
+                        name: "env-registry".to_string(),
+                        ..SecretEnvSource::default()
+                    }),
+                    ..EnvFromSource::default()
+                }]),
+                ..Container::default()
+            }],
+            image_pull_secrets: Some(vec![LocalObjectReference {
+                name: "registry".to_string(),
+            }]),
+            ..PodSpec::default()
+        };
+        let secret_types = HashMap::from([
+            ("registry", "kubernetes.io/dockerconfigjson"),
+            ("env-registry", "kubernetes.io/dockerconfigjson"),
+        ]);
+        let referrers = Referrers {
+            namespace: "default",
+            ingresses: &[],
+            gateways: &[],
+            service_accounts: &[],
+        };
+        let mut cfgmaps_orphans = HashSet::new();
+        let mut secrets_orphans =
+            HashSet::from(["registry".to_string(), "env-registry".to_string()]);
+
+        remove_referenced(
+            &mut cfgmaps_orphans,
+            &mut secrets_orphans,
+            &secret_types,
+            &[&pod_spec],
+            &referrers,
+        );
+        assert_eq!(secrets_orphans, HashSet::from(["env-registry".to_string()]));
+    }
+
+    #[test]
+    fn referenced_by_init_container_and_projected_volume() {
+        let pod_spec = PodSpec {
+            init_containers: Some(vec![Container {
+                name: "migrate".to_string(),
+                env_from: Some(vec![EnvFromSource {
+                    secret_ref: Some(SecretEnvSource {
+                        name: "database".to_string(),
+                        ..SecretEnvSource::default()
+                    }),
+                    ..EnvFromSource::default()
+                }]),
+                ..Container::default()
+            }]),
+            volumes: Some(vec![
+                Volume {
+                    name: "config".to_string(),
+                    projected: Some(ProjectedVolumeSource {
+                        sources: Some(vec![VolumeProjection {
+                            config_map: Some(ConfigMapProjection {
+                                name: "settings".to_string(),
+                                ..ConfigMapProjection::default()
+                            }),
+                            ..VolumeProjection::default()
+                        }]),
+                        ..ProjectedVolumeSource::default()
+                    }),
+                    ..Volume::default()
+                },
+                Volume {
+                    name: "unnamed".to_string(),
+                    secret: Some(SecretVolumeSource::default()),
+                    ..Volume::default()
+                },
+            ]),
+            ..PodSpec::default()
+        };
+        let referrers = Referrers {
+            namespace: "default",
+            ingresses: &[],
+            gateways: &[],
+            service_accounts: &[],
+        };
+        let mut cfgmaps_orphans = HashSet::from(["settings".to_string(), "unused".to_string()]);
+        let mut secrets_orphans = HashSet::from(["database".to_string()]);
+
+        remove_referenced(
+            &mut cfgmaps_orphans,
+            &mut secrets_orphans,
+            &HashMap::new(),
+            &[&pod_spec],
+            &referrers,
+        );
+        assert_eq!(cfgmaps_orphans, HashSet::from(["unused".to_string()]));
+        assert!(secrets_orphans.is_empty());
+    }
+
+    #[test]
+    fn rules_keep_unreferenced_objects_in_use() {
+        let secret = |name: &str, type_: &str, service_account: Option<&str>| Secret {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                annotations: service_account.map(|service_account| {
+                    BTreeMap::from([(
+                        "kubernetes.io/service-account.name".to_string(),
+                        service_account.to_string(),
+                    )])
+                }),
+                ..ObjectMeta::default()
+            },
+            type_: Some(type_.to_string()),
+            ..Secret::default()
+        };
+        let release = secret("sh.helm.release.v1.app.v1", "helm.sh/release.v1", None);
+        let builder_token = secret(
+            "builder-token",
+            "kubernetes.io/service-account-token",
+            Some("builder"),
+        );
+        let deployer_token = secret(
+            "deployer-token",
+            "kubernetes.io/service-account-token",
+            Some("deployer"),
+        );
+        let service_accounts = vec![ServiceAccount {
+            metadata: ObjectMeta {
+                name: Some("builder".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ServiceAccount::default()
+        }];
+        let secrets = vec![builder_token.clone(), deployer_token.clone()];
+        let orphan_tokens = find_orphan_tokens(&secrets, &service_accounts);
+        let now = Utc::now();
+
+        assert_eq!(
+            configmap_rule("kube-root-ca.crt"),
+            Some("root CA certificate")
+        );
+        assert_eq!(configmap_rule("settings"), None);
+        assert_eq!(
+            secret_rule(&release, &orphan_tokens, now),
+            Some("Helm release")
+        );
+        assert_eq!(
+            secret_rule(&builder_token, &orphan_tokens, now),
+            Some("service account token")
+        );
+        assert_eq!(secret_rule(&deployer_token, &orphan_tokens, now), None);
+
+        let (_, secrets_names, _) = orphan_candidates(&[], &secrets, now);
+        assert!(secrets_names.is_empty());
+        assert_eq!(
+            secret_rule(&secret("app", "Opaque", None), &orphan_tokens, now),
+            None
+        );
+    }
+
+    #[test]
+    fn orphans_footprint_sums_orphan_sizes() {
+        let metadata = |name: &str| ObjectMeta {
+            name: Some(name.to_string()),
+            ..ObjectMeta::default()
+        };
+        let cfgmap = |name: &str| ConfigMap {
+            metadata: metadata(name),
+            data: Some(BTreeMap::from([("key".to_string(), name.repeat(100))])),
+            ..ConfigMap::default()
+        };
+        let resources = NamespaceResources {
+            cfgmaps: vec![cfgmap("used"), cfgmap("orphan")],
+            secrets: vec![Secret {
+                metadata: metadata("orphan-secret"),
+                data: Some(BTreeMap::from([(
+                    "key".to_string(),
+                    ByteString(b"value".to_vec()),
+                )])),
+                ..Secret::default()
+            }],
+            pods: vec![Pod {
+                metadata: metadata("app"),
+                spec: Some(PodSpec {
+                    volumes: Some(vec![Volume {
+                        name: "config".to_string(),
+                        config_map: Some(ConfigMapVolumeSource {
+                            name: "used".to_string(),
+                            ..ConfigMapVolumeSource::default()
+                        }),
+                        ..Volume::default()
+                    }]),
+                    ..PodSpec::default()
+                }),
+                ..Pod::default()
+            }],
+            ..NamespaceResources::default()
+        };
+
+        let orphans = detect_orphans("default", &resources, &OrphanOptions::default(), Utc::now());
+
+        let cfgmaps_size = encoded_size(&resources.cfgmaps[1]);
+        let secrets_size = encoded_size(&resources.secrets[0]);
+        assert_eq!(
+            orphans.sizes.orphans_footprint,
+            Footprint {
+                configmaps: cfgmaps_size,
+                secrets: secrets_size,
+                total: cfgmaps_size + secrets_size,
+            }
+        );
+    }
 }
@@ -0,0 +1,159 @@
+use std::collections::{BTreeMap, HashSet};
+
+use k8s_openapi::api::core::v1::Namespace;
+use k8s_openapi::api::networking::v1::{NetworkPolicy, NetworkPolicyPeer};
+use serde::Serialize;
+
+use crate::selector::selector_matches;
+
+/// An ingress or egress peer of a `NetworkPolicy` with a `namespaceSelector` matching no namespace.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DanglingPeer {
+    pub network_policy: String,
+    pub path: String,
+}
+
+/// Finds `NetworkPolicy`s whose `podSelector` matches none of the pods or pod templates
+/// represented by `pod_labels`.
+pub fn find_orphan_network_policies(
+    network_policies: &[NetworkPolicy],
+    pod_labels: &[Option<&BTreeMap<String, String>>],
+) -> HashSet<String> {
+    network_policies
+        .iter()
+        .filter(|network_policy| {
+            network_policy.spec.as_ref().is_some_and(|spec| {
+                !pod_labels
+                    .iter()
+                    .any(|labels| selector_matches(&spec.pod_selector, *labels))
+            })
+        })
+        .filter_map(|network_policy| network_policy.metadata.name.clone())
+        .collect()
+}
+
+/// Finds ingress and egress peers with a `namespaceSelector` matching none of the `namespaces`.
+pub fn find_dangling_peers(
+    network_policies: &[NetworkPolicy],
+    namespaces: &[Namespace],
+) -> Vec<DanglingPeer> {
+    let mut dangling_peers = Vec::new();
+    for network_policy in network_policies {
+        let (Some(name), Some(spec)) = (&network_policy.metadata.name, &network_policy.spec) else {
+            continue;
+        };
+
+        let ingress_peers =
+            spec.ingress
+                .iter()
+                .flatten()
+                .enumerate()
+                .flat_map(|(rule_idx, rule)| {
+                    rule.from
+                        .iter()
+                        .flatten()
+                        .enumerate()
+                        .map(move |(peer_idx, peer)| {
+                            (format!("spec.ingress[{rule_idx}].from[{peer_idx}]"), peer)
+                        })
+                });
+        let egress_peers = spec
+            .egress
+            .iter()
+            .flatten()
+            .enumerate()
+            .flat_map(|(rule_idx, rule)| {
+                rule.to
+                    .iter()
+                    .flatten()
+                    .enumerate()
+                    .map(move |(peer_idx, peer)| {
+                        (format!("spec.egress[{rule_idx}].to[{peer_idx}]"), peer)
+                    })
+            });
+
+        ingress_peers
+            .chain(egress_peers)
+            .filter(|(_, peer)| !selects_any_namespace(peer, namespaces))
+            .for_each(|(path, _)| {
+                dangling_peers.push(DanglingPeer {
+                    network_policy: name.clone(),
+                    path: format!("{path}.namespaceSelector"),
+                })
+            });
+    }
+    dangling_peers
+}
+
+fn selects_any_namespace(peer: &NetworkPolicyPeer, namespaces: &[Namespace]) -> bool {
+    match &peer.namespace_selector {
+        None => true,
+        Some(selector) => namespaces
+            .iter()
+            .any(|namespace| selector_matches(selector, namespace.metadata.labels.as_ref())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::api::core::v1::Namespace;
+    use k8s_openapi::api::networking::v1::{
+        NetworkPolicy, NetworkPolicyIngressRule, NetworkPolicyPeer, NetworkPolicySpec,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::network_policies::{
+        find_dangling_peers, find_orphan_network_policies, DanglingPeer,
+    };
+
+    fn selector(key: &str, value: &str) -> LabelSelector {
+        LabelSelector {
+            match_labels: Some(BTreeMap::from([(key.to_string(), value.to_string())])),
+            ..LabelSelector::default()
+        }
+    }
+
+    #[test]
+    fn network_policy_selecting_nothing() {
+        let network_policies = vec![NetworkPolicy {
+            metadata: ObjectMeta {
+                name: Some("allow-frontend".to_string()),
+                ..ObjectMeta::default()
+            },
+            spec: Some(NetworkPolicySpec {
+                pod_selector: selector("app", "backend"),
+                ingress: Some(vec![NetworkPolicyIngressRule {
+                    from: Some(vec![NetworkPolicyPeer {
+                        namespace_selector: Some(selector("team", "removed")),
+                        ..NetworkPolicyPeer::default()
+                    }]),
+                    ..NetworkPolicyIngressRule::default()
+                }]),
+                ..NetworkPolicySpec::default()
+            }),
+        }];
+        let frontend_labels = BTreeMap::from([("app".to_string(), "frontend".to_string())]);
+        let namespace = Namespace {
+            metadata: ObjectMeta {
+                labels: Some(BTreeMap::from([("team".to_string(), "web".to_string())])),
+                ..ObjectMeta::default()
+            },
+            ..Namespace::default()
+        };
+
+        let orphans = find_orphan_network_policies(&network_policies, &[Some(&frontend_labels)]);
+        assert!(orphans.contains("allow-frontend"));
+
+        let dangling_peers = find_dangling_peers(&network_policies, &[namespace]);
+        assert_eq!(
+            dangling_peers,
+            vec![DanglingPeer {
+                network_policy: "allow-frontend".to_string(),
+                path: "spec.ingress[0].from[0].namespaceSelector".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+
+use k8s_openapi::api::core::v1::ServiceAccount;
+use k8s_openapi::api::rbac::v1::{
+    ClusterRole, ClusterRoleBinding, Role, RoleBinding, RoleRef, Subject,
+};
+use serde::Serialize;
+
+/// A `RoleBinding` or `ClusterRoleBinding` referencing missing `ServiceAccount`s or a missing role.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DanglingBinding {
+    pub name: String,
+    pub missing_service_accounts: Vec<String>,
+    pub missing_role: Option<String>,
+}
+
+/// Finds `RoleBinding`s with `subjects` referencing missing `ServiceAccount`s of the `namespace`,
+/// or with a `roleRef` pointing to a missing `Role` or `ClusterRole`. When `cluster_roles` are unknown,
+/// references to a `ClusterRole` are not checked.
+pub fn find_dangling_role_bindings(
+    namespace: &str,
+    role_bindings: &[RoleBinding],
+    roles: &[Role],
+    cluster_roles: Option<&[ClusterRole]>,
+    service_accounts: &[ServiceAccount],
+) -> Vec<DanglingBinding> {
+    let service_account_names = names(service_accounts.iter().map(|sa| &sa.metadata.name));
+    let role_names = names(roles.iter().map(|role| &role.metadata.name));
+    let cluster_role_names = cluster_role_names(cluster_roles);
+
+    role_bindings
+        .iter()
+        .filter_map(|binding| {
+            dangling_binding(
+                binding.metadata.name.as_ref()?,
+                binding.subjects.as_deref().unwrap_or_default(),
+                &binding.role_ref,
+                namespace,
+                &service_account_names,
+                &role_names,
+                cluster_role_names.as_ref(),
+            )
+        })
+        .collect()
+}
+
+/// Finds `ClusterRoleBinding`s with `subjects` referencing missing `ServiceAccount`s of the `namespace`.
+/// Only bindings with a `ServiceAccount` subject in the `namespace` are inspected, as service accounts
+/// of other namespaces are unknown.
+pub fn find_dangling_cluster_role_bindings(
+    namespace: &str,
+    cluster_role_bindings: &[ClusterRoleBinding],
+    cluster_roles: Option<&[ClusterRole]>,
+    service_accounts: &[ServiceAccount],
+) -> Vec<DanglingBinding> {
+    let service_account_names = names(service_accounts.iter().map(|sa| &sa.metadata.name));
+    let cluster_role_names = cluster_role_names(cluster_roles);
+
+    cluster_role_bindings
+        .iter()
+        .filter(|binding| {
+            binding
+                .subjects
+                .iter()
+                .flatten()
+                .any(|subject| is_service_account_in(subject, namespace, None))
+        })
+        .filter_map(|binding| {
+            dangling_binding(
+                binding.metadata.name.as_ref()?,
+                binding.subjects.as_deref().unwrap_or_default(),
+                &binding.role_ref,
+                namespace,
+                &service_account_names,
+                &HashSet::new(),
+                cluster_role_names.as_ref(),
+            )
+        })
+        .collect()
+}
+
+/// Finds `Role`s not referenced by any of the `role_bindings`.
+pub fn find_unbound_roles(roles: &[Role], role_bindings: &[RoleBinding]) -> HashSet<String> {
+    let bound_roles: HashSet<&str> = role_bindings
+        .iter()
+        .filter(|binding| binding.role_ref.kind == "Role")
+        .map(|binding| binding.role_ref.name.as_str())
+        .collect();
+
+    roles
+        .iter()
+        .filter_map(|role| role.metadata.name.as_ref())
+        .filter(|role| !bound_roles.contains(role.as_str()))
+        .cloned()
+        .collect()
+}
+
+fn dangling_binding(
+    name: &str,
+    subjects: &[Subject],
+    role_ref: &RoleRef,
+    namespace: &str,
+    service_account_names: &HashSet<&str>,
+    role_names: &HashSet<&str>,
+    cluster_role_names: Option<&HashSet<&str>>,
+) -> Option<DanglingBinding> {
+    let missing_service_accounts: Vec<String> = subjects
+        .iter()
+        .filter(|subject| is_service_account_in(subject, namespace, Some(namespace)))
+        .filter(|subject| !service_account_names.contains(subject.name.as_str()))
+        .map(|subject| subject.name.clone())
+        .collect();
+
+    let role_exists = match role_ref.kind.as_str() {
+        "Role" => role_names.contains(role_ref.name.as_str()),
+        "ClusterRole" => {
+            cluster_role_names.is_none_or(|names| names.contains(role_ref.name.as_str()))
+        }
+        _ => true,
+    };
+    let missing_role = (!role_exists).then(|| format!("{}/{}", role_ref.kind, role_ref.name));
+
+    if missing_service_accounts.is_empty() && missing_role.is_none() {
+        return None;
+    }
+    Some(DanglingBinding {
+        name: name.to_string(),
+        missing_service_accounts,
+        missing_role,
+    })
+}
+
+/// Checks whether the `subject` is a `ServiceAccount` of the `namespace`. Subjects without a namespace
+/// are considered to be in the `default_namespace`, if any.
+fn is_service_account_in(
+    subject: &Subject,
+    namespace: &str,
+    default_namespace: Option<&str>,
+) -> bool {
+    subject.kind == "ServiceAccount"
+        && subject.namespace.as_deref().or(default_namespace) == Some(namespace)
+}
+
+fn cluster_role_names(cluster_roles: Option<&[ClusterRole]>) -> Option<HashSet<&str>> {
+    cluster_roles.map(|cluster_roles| names(cluster_roles.iter().map(|role| &role.metadata.name)))
+}
+
+fn names<'a>(names: impl Iterator<Item = &'a Option<String>>) -> HashSet<&'a str> {
+    names.filter_map(|name| name.as_deref()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::ServiceAccount;
+    use k8s_openapi::api::rbac::v1::{
+        ClusterRole, ClusterRoleBinding, Role, RoleBinding, RoleRef, Subject,
+    };
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::rbac::{
+        find_dangling_cluster_role_bindings, find_dangling_role_bindings, find_unbound_roles,
+        DanglingBinding,
+    };
+
+    fn metadata(name: &str) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            ..ObjectMeta::default()
+        }
+    }
+
+    #[test]
+    fn role_binding_to_missing_service_account_and_role() {
+        let service_accounts = vec![ServiceAccount {
+            metadata: metadata("existing-sa"),
+            ..ServiceAccount::default()
+        }];
+        let roles = vec![
+            Role {
+                metadata: metadata("bound-role"),
+                ..Role::default()
+            },
+            Role {
+                metadata: metadata("unbound-role"),
+                ..Role::default()
+            },
+        ];
+        let service_account_subject = |name: &str| Subject {
+            kind: "ServiceAccount".to_string(),
+            name: name.to_string(),
+            namespace: Some("default".to_string()),
+            ..Subject::default()
+        };
+        let role_bindings = vec![
+            RoleBinding {
+                metadata: metadata("valid-binding"),
+                role_ref: RoleRef {
+                    api_group: "rbac.authorization.k8s.io".to_string(),
+                    kind: "Role".to_string(),
+                    name: "bound-role".to_string(),
+                },
+                subjects: Some(vec![service_account_subject("existing-sa")]),
+            },
+            RoleBinding {
+                metadata: metadata("dangling-binding"),
+                role_ref: RoleRef {
+                    api_group: "rbac.authorization.k8s.io".to_string(),
+                    kind: "Role".to_string(),
+                    name: "removed-role".to_string(),
+                },
+                subjects: Some(vec![
+                    service_account_subject("existing-sa"),
+                    service_account_subject("removed-sa"),
+                ]),
+            },
+        ];
+
+        let dangling_bindings =
+            find_dangling_role_bindings("default", &role_bindings, &roles, None, &service_accounts);
+        assert_eq!(
+            dangling_bindings,
+            vec![DanglingBinding {
+                name: "dangling-binding".to_string(),
+                missing_service_accounts: vec!["removed-sa".to_string()],
+                missing_role: Some("Role/removed-role".to_string()),
+            }]
+        );
+
+        let unbound_roles = find_unbound_roles(&roles, &role_bindings);
+        assert_eq!(unbound_roles.len(), 1);
+        assert!(unbound_roles.contains("unbound-role"));
+    }
+
+    #[test]
+    fn cluster_role_binding_to_missing_service_account_and_cluster_role() {
+        let service_accounts = vec![ServiceAccount {
+            metadata: metadata("existing-sa"),
+            ..ServiceAccount::default()
+        }];
+        let cluster_roles = vec![ClusterRole {
+            metadata: metadata("view"),
+            ..ClusterRole::default()
+        }];
+        let subject = |name: &str, namespace: &str| Subject {
+            kind: "ServiceAccount".to_string(),
+            name: name.to_string(),
+            namespace: Some(namespace.to_string()),
+            ..Subject::default()
+        };
+        let cluster_role_binding =
+            |name: &str, role: &str, subjects: Vec<Subject>| ClusterRoleBinding {
+                metadata: metadata(name),
+                role_ref: RoleRef {
+                    api_group: "rbac.authorization.k8s.io".to_string(),
+                    kind: "ClusterRole".to_string(),
+                    name: role.to_string(),
+                },
+                subjects: Some(subjects),
+            };
+        let cluster_role_bindings = vec![
+            cluster_role_binding(
+                "valid-binding",
+                "view",
+                vec![subject("existing-sa", "default")],
+            ),
+            cluster_role_binding(
+                "dangling-binding",
+                "removed-role",
+                vec![subject("removed-sa", "default")],
+            ),
+            cluster_role_binding(
+                "other-namespace-binding",
+                "removed-role",
+                vec![subject("removed-sa", "other")],
+            ),
+        ];
+
+        assert_eq!(
+            find_dangling_cluster_role_bindings(
+                "default",
+                &cluster_role_bindings,
+                Some(&cluster_roles),
+                &service_accounts,
+            ),
+            vec![DanglingBinding {
+                name: "dangling-binding".to_string(),
+                missing_service_accounts: vec!["removed-sa".to_string()],
+                missing_role: Some("ClusterRole/removed-role".to_string()),
+            }]
+        );
+
+        // Without access to ClusterRoles, only ServiceAccounts are checked.
+        assert_eq!(
+            find_dangling_cluster_role_bindings(
+                "default",
+                &cluster_role_bindings,
+                None,
+                &service_accounts,
+            ),
+            vec![DanglingBinding {
+                name: "dangling-binding".to_string(),
+                missing_service_accounts: vec!["removed-sa".to_string()],
+                missing_role: None,
+            }]
+        );
+    }
+}
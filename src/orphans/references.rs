@@ -0,0 +1,795 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::Result;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::{
+    ConfigMap, Pod, PodSpec, ReplicationController, Secret, ServiceAccount,
+};
+use k8s_openapi::api::networking::v1::Ingress;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::chrono::Utc;
+use kube::Client;
+use serde::Serialize;
+
+use crate::orphans::secret_types::gateway_api_resource;
+use crate::orphans::tokens::find_orphan_tokens;
+use crate::orphans::{configmap_rule, secret_rule, Referrers};
+use crate::pod_spec::ResourceWithPodSpec;
+use crate::resources::{list_optional_resource, list_resource};
+use crate::workloads::WorkloadKind;
+
+/// Owner chains are followed up to this depth, guarding against cycles.
+pub(crate) const MAX_OWNER_DEPTH: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ObjectKind {
+    ConfigMap,
+    Secret,
+}
+
+impl ObjectKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ObjectKind::ConfigMap => "ConfigMap",
+            ObjectKind::Secret => "Secret",
+        }
+    }
+}
+
+impl Display for ObjectKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A `ConfigMap` or `Secret` given on the command line as `kind/name`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectRef {
+    pub kind: ObjectKind,
+    pub name: String,
+}
+
+impl ObjectRef {
+    fn new(kind: ObjectKind, name: &str) -> Self {
+        ObjectRef {
+            kind,
+            name: name.to_string(),
+        }
+    }
+}
+
+impl FromStr for ObjectRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((kind, name)) = s.split_once('/') else {
+            return Err("Expected kind/name, e.g. 'configmap/foo'".to_string());
+        };
+        let kind = match kind.to_lowercase().as_str() {
+            "configmap" | "configmaps" | "cm" => ObjectKind::ConfigMap,
+            "secret" | "secrets" => ObjectKind::Secret,
+            _ => return Err(format!("Unsupported kind '{kind}'")),
+        };
+        if name.is_empty() {
+            return Err("Missing name".to_string());
+        }
+        Ok(ObjectRef::new(kind, name))
+    }
+}
+
+impl Display for ObjectRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.kind, self.name)
+    }
+}
+
+/// A resource referencing a `ConfigMap` or `Secret`. The owner chain lists the referrer's
+/// controller first, e.g. `ReplicaSet/app-5d4f8` followed by `Deployment/app`.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct Reference {
+    pub kind: String,
+    pub name: String,
+    pub owners: Vec<String>,
+    /// The container making the reference, unless made by the pod spec or another resource.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    pub path: String,
+}
+
+pub type ReferenceIndex = HashMap<ObjectRef, Vec<Reference>>;
+
+/// A resource with a pod spec.
+pub struct PodSpecReferrer<'a> {
+    pub(crate) kind: WorkloadKind,
+    pub(crate) metadata: &'a ObjectMeta,
+    pub(crate) pod_spec: &'a PodSpec,
+}
+
+/// UIDs of the owners of the object with given `metadata`.
+pub(crate) fn owner_uids(metadata: &ObjectMeta) -> impl Iterator<Item = &str> {
+    metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .map(|owner| owner.uid.as_str())
+}
+
+pub fn extend_with_referrers<'a, T>(referrers: &mut Vec<PodSpecReferrer<'a>>, resources: &'a [T])
+where
+    T: ResourceWithPodSpec + k8s_openapi::Resource + k8s_openapi::Metadata<Ty = ObjectMeta>,
+{
+    let ext_referrers = resources.iter().filter_map(|resource| {
+        Some(PodSpecReferrer {
+            kind: T::WORKLOAD_KIND,
+            metadata: resource.metadata(),
+            pod_spec: resource.pod_template_spec()?,
+        })
+    });
+    referrers.extend(ext_referrers);
+}
+
+/// References, with their JSON path, of a `ConfigMap` or `Secret` in the object. Objects in use
+/// without being referenced, e.g. Helm release `Secret`s, name the rule instead.
+#[derive(Serialize)]
+pub struct ObjectReferences {
+    pub object: String,
+    pub exists: bool,
+    pub references: Vec<Reference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<&'static str>,
+}
+
+/// Looks up every reference on the `objects` in the `namespace`.
+pub async fn find_object_references(
+    client: &Client,
+    namespace: &str,
+    objects: &[ObjectRef],
+) -> Result<Vec<ObjectReferences>> {
+    let gateway_resource = gateway_api_resource();
+    let (
+        cfgmaps,
+        secrets,
+        deployments,
+        replicasets,
+        statefulsets,
+        daemonsets,
+        jobs,
+        cronjobs,
+        replication_controllers,
+        pods,
+        ingresses,
+        service_accounts,
+        gateways,
+    ) = tokio::try_join!(
+        list_resource::<ConfigMap>(client, namespace),
+        list_resource::<Secret>(client, namespace),
+        list_resource::<Deployment>(client, namespace),
+        list_resource::<ReplicaSet>(client, namespace),
+        list_resource::<StatefulSet>(client, namespace),
+        list_resource::<DaemonSet>(client, namespace),
+        list_resource::<Job>(client, namespace),
+        list_resource::<CronJob>(client, namespace),
+        list_resource::<ReplicationController>(client, namespace),
+        list_resource::<Pod>(client, namespace),
+        list_resource::<Ingress>(client, namespace),
+        list_resource::<ServiceAccount>(client, namespace),
+        list_optional_resource(client, namespace, &gateway_resource)
+    )?;
+
+    let mut pod_spec_referrers = Vec::new();
+    extend_with_referrers(&mut pod_spec_referrers, &deployments);
+    extend_with_referrers(&mut pod_spec_referrers, &replicasets);
+    extend_with_referrers(&mut pod_spec_referrers, &statefulsets);
+    extend_with_referrers(&mut pod_spec_referrers, &daemonsets);
+    extend_with_referrers(&mut pod_spec_referrers, &jobs);
+    extend_with_referrers(&mut pod_spec_referrers, &cronjobs);
+    extend_with_referrers(&mut pod_spec_referrers, &replication_controllers);
+    extend_with_referrers(&mut pod_spec_referrers, &pods);
+    let referrers = Referrers {
+        namespace,
+        ingresses: &ingresses,
+        gateways: gateways.as_deref().unwrap_or_default(),
+        service_accounts: &service_accounts,
+    };
+    let mut index = index_references(&pod_spec_referrers, &referrers);
+    let orphan_tokens = find_orphan_tokens(&secrets, &service_accounts);
+    let now = Utc::now();
+
+    Ok(objects
+        .iter()
+        .map(|object| {
+            let (exists, rule) = match object.kind {
+                ObjectKind::ConfigMap => {
+                    let exists = has_name(cfgmaps.iter().map(|c| &c.metadata), &object.name);
+                    (exists, configmap_rule(&object.name))
+                }
+                ObjectKind::Secret => {
+                    let secret = secrets
+                        .iter()
+                        .find(|secret| secret.metadata.name.as_deref() == Some(&object.name));
+                    let rule = secret.and_then(|secret| secret_rule(secret, &orphan_tokens, now));
+                    (secret.is_some(), rule)
+                }
+            };
+            ObjectReferences {
+                object: object.to_string(),
+                exists,
+                references: index.remove(object).unwrap_or_default(),
+                rule: rule.filter(|_| exists),
+            }
+        })
+        .collect())
+}
+
+fn has_name<'a>(mut metadata: impl Iterator<Item = &'a ObjectMeta>, name: &str) -> bool {
+    metadata.any(|metadata| metadata.name.as_deref() == Some(name))
+}
+
+/// Indexes references on `ConfigMap`s and `Secret`s made by pod specs and other `referrers`.
+pub(super) fn index_references(
+    pod_spec_referrers: &[PodSpecReferrer],
+    referrers: &Referrers,
+) -> ReferenceIndex {
+    let controllers: HashMap<(&str, &str), (&str, &str)> = pod_spec_referrers
+        .iter()
+        .filter_map(|referrer| {
+            let name = referrer.metadata.name.as_deref()?;
+            let controller = referrer
+                .metadata
+                .owner_references
+                .iter()
+                .flatten()
+                .find(|owner| owner.controller == Some(true))?;
+            Some((
+                (referrer.kind.as_str(), name),
+                (controller.kind.as_str(), controller.name.as_str()),
+            ))
+        })
+        .collect();
+    let owner_chain = |kind: &str, name: &str| -> Vec<String> {
+        let mut owners = Vec::new();
+        let mut current = (kind, name);
+        while let Some(&owner) = controllers.get(&current) {
+            if owners.len() == MAX_OWNER_DEPTH {
+                break;
+            }
+            owners.push(format!("{}/{}", owner.0, owner.1));
+            current = owner;
+        }
+        owners
+    };
+
+    let mut index = ReferenceIndex::new();
+    let mut add = |object: ObjectRef,
+                   kind: &str,
+                   name: &str,
+                   owners: Vec<String>,
+                   container: Option<String>,
+                   path: String| {
+        index.entry(object).or_default().push(Reference {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            owners,
+            container,
+            path,
+        })
+    };
+
+    for referrer in pod_spec_referrers {
+        let Some(name) = referrer.metadata.name.as_deref() else {
+            continue;
+        };
+        let kind = referrer.kind.as_str();
+        for reference in pod_spec_references(referrer.pod_spec, referrer.kind.pod_spec_path()) {
+            add(
+                reference.object,
+                kind,
+                name,
+                owner_chain(kind, name),
+                reference.container,
+                reference.path,
+            );
+        }
+    }
+
+    for (kind, name, object, path) in referrers_references(referrers) {
+        add(object, kind, name, Vec::new(), None, path);
+    }
+    index
+}
+
+/// References on `ConfigMap`s and `Secret`s made by `referrers` other than pod specs, with the kind
+/// and name of the referrer.
+pub(super) fn referrers_references<'a>(
+    referrers: &Referrers<'a>,
+) -> Vec<(&'static str, &'a str, ObjectRef, String)> {
+    let mut references = Vec::new();
+    for ingress in referrers.ingresses {
+        let Some(name) = ingress.metadata.name.as_deref() else {
+            continue;
+        };
+        let tls = ingress
+            .spec
+            .iter()
+            .flat_map(|spec| spec.tls.iter().flatten());
+        for (idx, tls) in tls.enumerate() {
+            if let Some(secret) = &tls.secret_name {
+                let path = format!("spec.tls[{idx}].secretName");
+                let reference = ObjectRef::new(ObjectKind::Secret, secret);
+                references.push(("Ingress", name, reference, path));
+            }
+        }
+    }
+
+    for gateway in referrers.gateways {
+        let Some(name) = gateway.metadata.name.as_deref() else {
+            continue;
+        };
+        let listeners = gateway.data["spec"]["listeners"]
+            .as_array()
+            .into_iter()
+            .flatten();
+        for (listener_idx, listener) in listeners.enumerate() {
+            let certificate_refs = listener["tls"]["certificateRefs"].as_array();
+            for (ref_idx, certificate_ref) in certificate_refs.into_iter().flatten().enumerate() {
+                let is_local_secret = certificate_ref["kind"].as_str().unwrap_or("Secret")
+                    == "Secret"
+                    && certificate_ref["namespace"]
+                        .as_str()
+                        .is_none_or(|namespace| namespace == referrers.namespace);
+                if let (true, Some(secret)) = (is_local_secret, certificate_ref["name"].as_str()) {
+                    let path =
+                        format!("spec.listeners[{listener_idx}].tls.certificateRefs[{ref_idx}]");
+                    let reference = ObjectRef::new(ObjectKind::Secret, secret);
+                    references.push(("Gateway", name, reference, path));
+                }
+            }
+        }
+    }
+
+    for service_account in referrers.service_accounts {
+        let Some(name) = service_account.metadata.name.as_deref() else {
+            continue;
+        };
+        let secrets = service_account.secrets.iter().flatten();
+        for (idx, secret) in secrets.enumerate() {
+            if let Some(secret) = &secret.name {
+                let path = format!("secrets[{idx}]");
+                let reference = ObjectRef::new(ObjectKind::Secret, secret);
+                references.push(("ServiceAccount", name, reference, path));
+            }
+        }
+        let pull_secrets = service_account.image_pull_secrets.iter().flatten();
+        for (idx, secret) in pull_secrets.enumerate() {
+            let path = format!("imagePullSecrets[{idx}]");
+            let reference = ObjectRef::new(ObjectKind::Secret, &secret.name);
+            references.push(("ServiceAccount", name, reference, path));
+        }
+    }
+    references
+}
+
+/// Whether the reference at `path` is an `imagePullSecrets` entry.
+pub(super) fn is_image_pull_secrets_path(path: &str) -> bool {
+    path.rsplit('.')
+        .next()
+        .is_some_and(|field| field.starts_with("imagePullSecrets["))
+}
+
+/// A `ConfigMap` or `Secret` in use, with the references keeping it alive. Objects used without being
+/// referenced, e.g. Helm release `Secret`s, name the rule instead.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Explanation {
+    pub name: String,
+    pub references: Vec<Reference>,
+    pub rule: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct Explained {
+    pub configmaps: Vec<Explanation>,
+    pub secrets: Vec<Explanation>,
+}
+
+/// Explains why `objects` of given `kind` are in use, given their names and the rule exempting them
+/// from orphan detection, if any.
+pub(super) fn explain<'a>(
+    kind: ObjectKind,
+    objects: impl Iterator<Item = (&'a str, Option<&'static str>)>,
+    index: &ReferenceIndex,
+) -> Vec<Explanation> {
+    let mut explanations: Vec<Explanation> = objects
+        .map(|(name, rule)| Explanation {
+            name: name.to_string(),
+            references: index
+                .get(&ObjectRef::new(kind, name))
+                .cloned()
+                .unwrap_or_default(),
+            rule,
+        })
+        .collect();
+    explanations.sort_by(|a, b| a.name.cmp(&b.name));
+    explanations
+}
+
+/// A reference on a `ConfigMap` or `Secret` in a pod spec.
+#[derive(Debug, PartialEq)]
+pub struct PodSpecReference {
+    pub object: ObjectRef,
+    pub path: String,
+    /// The container making the reference, `None` for volumes and `imagePullSecrets`.
+    pub container: Option<String>,
+    /// Whether the reference is marked `optional`, i.e. pods start without the object.
+    pub optional: bool,
+}
+
+/// References on `ConfigMap`s and `Secret`s in the `pod_spec` found at `path`.
+pub fn pod_spec_references(pod_spec: &PodSpec, path: &str) -> Vec<PodSpecReference> {
+    let mut references = Vec::new();
+    let mut add = |kind: ObjectKind,
+                   name: &str,
+                   path: String,
+                   container: Option<&str>,
+                   optional: Option<bool>| {
+        references.push(PodSpecReference {
+            object: ObjectRef::new(kind, name),
+            path,
+            container: container.map(str::to_string),
+            optional: optional.unwrap_or(false),
+        })
+    };
+    let init_containers =
+        pod_spec
+            .init_containers
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(idx, container)| {
+                (
+                    format!("{path}.initContainers[{idx}]"),
+                    container.name.as_str(),
+                    container.env_from.as_deref(),
+                    container.env.as_deref(),
+                )
+            });
+    let containers = pod_spec
+        .containers
+        .iter()
+        .enumerate()
+        .map(|(idx, container)| {
+            (
+                format!("{path}.containers[{idx}]"),
+                container.name.as_str(),
+                container.env_from.as_deref(),
+                container.env.as_deref(),
+            )
+        });
+    let ephemeral_containers = pod_spec
+        .ephemeral_containers
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(idx, container)| {
+            (
+                format!("{path}.ephemeralContainers[{idx}]"),
+                container.name.as_str(),
+                container.env_from.as_deref(),
+                container.env.as_deref(),
+            )
+        });
+    for (path, container, env_from, env) in init_containers
+        .chain(containers)
+        .chain(ephemeral_containers)
+    {
+        let container = Some(container);
+        for (idx, env_from) in env_from.into_iter().flatten().enumerate() {
+            if let Some(cfgmap) = &env_from.config_map_ref {
+                let path = format!("{path}.envFrom[{idx}].configMapRef");
+                add(
+                    ObjectKind::ConfigMap,
+                    &cfgmap.name,
+                    path,
+                    container,
+                    cfgmap.optional,
+                );
+            }
+            if let Some(secret) = &env_from.secret_ref {
+                let path = format!("{path}.envFrom[{idx}].secretRef");
+                add(
+                    ObjectKind::Secret,
+                    &secret.name,
+                    path,
+                    container,
+                    secret.optional,
+                );
+            }
+        }
+        for (idx, env_var) in env.into_iter().flatten().enumerate() {
+            let Some(value_from) = &env_var.value_from else {
+                continue;
+            };
+            if let Some(cfgmap) = &value_from.config_map_key_ref {
+                let path = format!("{path}.env[{idx}].valueFrom.configMapKeyRef");
+                add(
+                    ObjectKind::ConfigMap,
+                    &cfgmap.name,
+                    path,
+                    container,
+                    cfgmap.optional,
+                );
+            }
+            if let Some(secret) = &value_from.secret_key_ref {
+                let path = format!("{path}.env[{idx}].valueFrom.secretKeyRef");
+                add(
+                    ObjectKind::Secret,
+                    &secret.name,
+                    path,
+                    container,
+                    secret.optional,
+                );
+            }
+        }
+    }
+
+    for (volume_idx, volume) in pod_spec.volumes.iter().flatten().enumerate() {
+        let path = format!("{path}.volumes[{volume_idx}]");
+        if let Some(cfgmap) = &volume.config_map {
+            let path = format!("{path}.configMap");
+            add(
+                ObjectKind::ConfigMap,
+                &cfgmap.name,
+                path,
+                None,
+                cfgmap.optional,
+            );
+        }
+        if let Some(secret) = &volume.secret {
+            if let Some(secret_name) = &secret.secret_name {
+                let path = format!("{path}.secret");
+                add(ObjectKind::Secret, secret_name, path, None, secret.optional);
+            }
+        }
+        let sources = volume
+            .projected
+            .iter()
+            .flat_map(|projected| projected.sources.iter().flatten());
+        for (idx, source) in sources.enumerate() {
+            if let Some(cfgmap) = &source.config_map {
+                let path = format!("{path}.projected.sources[{idx}].configMap");
+                add(
+                    ObjectKind::ConfigMap,
+                    &cfgmap.name,
+                    path,
+                    None,
+                    cfgmap.optional,
+                );
+            }
+            if let Some(secret) = &source.secret {
+                let path = format!("{path}.projected.sources[{idx}].secret");
+                add(
+                    ObjectKind::Secret,
+                    &secret.name,
+                    path,
+                    None,
+                    secret.optional,
+                );
+            }
+        }
+    }
+
+    for (idx, secret) in pod_spec.image_pull_secrets.iter().flatten().enumerate() {
+        let path = format!("{path}.imagePullSecrets[{idx}]");
+        add(ObjectKind::Secret, &secret.name, path, None, None);
+    }
+    references
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::apps::v1::{ReplicaSet, ReplicaSetSpec};
+    use k8s_openapi::api::core::v1::{
+        Container, EnvVar, EnvVarSource, EphemeralContainer, Pod, PodSpec, PodTemplateSpec,
+        ProjectedVolumeSource, SecretKeySelector, SecretProjection, ServiceAccount, Volume,
+        VolumeProjection,
+    };
+    use k8s_openapi::api::networking::v1::{Ingress, IngressSpec, IngressTLS};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::references::{
+        explain, extend_with_referrers, index_references, ObjectKind, ObjectRef, Reference,
+    };
+    use crate::orphans::Referrers;
+
+    fn metadata(name: &str, controller: Option<(&str, &str)>) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            owner_references: controller.map(|(kind, name)| {
+                vec![OwnerReference {
+                    kind: kind.to_string(),
+                    name: name.to_string(),
+                    controller: Some(true),
+                    ..OwnerReference::default()
+                }]
+            }),
+            ..ObjectMeta::default()
+        }
+    }
+
+    fn credentials_env() -> EnvVar {
+        EnvVar {
+            value_from: Some(EnvVarSource {
+                secret_key_ref: Some(SecretKeySelector {
+                    name: "credentials".to_string(),
+                    ..SecretKeySelector::default()
+                }),
+                ..EnvVarSource::default()
+            }),
+            ..EnvVar::default()
+        }
+    }
+
+    #[test]
+    fn references_with_owner_chain_and_path() {
+        let pods = vec![Pod {
+            metadata: metadata("app-5d4f8-x2x7q", Some(("ReplicaSet", "app-5d4f8"))),
+            spec: Some(PodSpec {
+                containers: vec![
+                    Container::default(),
+                    Container {
+                        name: "app".to_string(),
+                        env: Some(vec![credentials_env()]),
+                        ..Container::default()
+                    },
+                ],
+                ephemeral_containers: Some(vec![EphemeralContainer {
+                    name: "debugger".to_string(),
+                    env: Some(vec![credentials_env()]),
+                    ..EphemeralContainer::default()
+                }]),
+                ..PodSpec::default()
+            }),
+            ..Pod::default()
+        }];
+        let replicasets = vec![ReplicaSet {
+            metadata: metadata("app-5d4f8", Some(("Deployment", "app"))),
+            spec: Some(ReplicaSetSpec {
+                template: Some(PodTemplateSpec {
+                    spec: Some(PodSpec::default()),
+                    ..PodTemplateSpec::default()
+                }),
+                ..ReplicaSetSpec::default()
+            }),
+            ..ReplicaSet::default()
+        }];
+        let ingresses = vec![Ingress {
+            metadata: metadata("web", None),
+            spec: Some(IngressSpec {
+                tls: Some(vec![IngressTLS {
+                    secret_name: Some("credentials".to_string()),
+                    ..IngressTLS::default()
+                }]),
+                ..IngressSpec::default()
+            }),
+            ..Ingress::default()
+        }];
+
+        let mut pod_spec_referrers = Vec::new();
+        extend_with_referrers(&mut pod_spec_referrers, &replicasets);
+        extend_with_referrers(&mut pod_spec_referrers, &pods);
+        let referrers = Referrers {
+            namespace: "default",
+            ingresses: &ingresses,
+            gateways: &[],
+            service_accounts: &Vec::<ServiceAccount>::new(),
+        };
+        let index = index_references(&pod_spec_referrers, &referrers);
+
+        assert_eq!(
+            index[&ObjectRef::new(ObjectKind::Secret, "credentials")],
+            vec![
+                Reference {
+                    kind: "Pod".to_string(),
+                    name: "app-5d4f8-x2x7q".to_string(),
+                    owners: vec![
+                        "ReplicaSet/app-5d4f8".to_string(),
+                        "Deployment/app".to_string()
+                    ],
+                    container: Some("app".to_string()),
+                    path: "spec.containers[1].env[0].valueFrom.secretKeyRef".to_string(),
+                },
+                Reference {
+                    kind: "Pod".to_string(),
+                    name: "app-5d4f8-x2x7q".to_string(),
+                    owners: vec![
+                        "ReplicaSet/app-5d4f8".to_string(),
+                        "Deployment/app".to_string()
+                    ],
+                    container: Some("debugger".to_string()),
+                    path: "spec.ephemeralContainers[0].env[0].valueFrom.secretKeyRef".to_string(),
+                },
+                Reference {
+                    kind: "Ingress".to_string(),
+                    name: "web".to_string(),
+                    owners: Vec::new(),
+                    container: None,
+                    path: "spec.tls[0].secretName".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn explanations_with_references_or_rule() {
+        let pods = vec![Pod {
+            metadata: metadata("app", None),
+            spec: Some(PodSpec {
+                init_containers: Some(vec![Container {
+                    env: Some(vec![EnvVar {
+                        value_from: Some(EnvVarSource {
+                            secret_key_ref: Some(SecretKeySelector {
+                                name: "credentials".to_string(),
+                                ..SecretKeySelector::default()
+                            }),
+                            ..EnvVarSource::default()
+                        }),
+                        ..EnvVar::default()
+                    }]),
+                    ..Container::default()
+                }]),
+                volumes: Some(vec![Volume {
+                    projected: Some(ProjectedVolumeSource {
+                        sources: Some(vec![VolumeProjection {
+                            secret: Some(SecretProjection {
+                                name: "credentials".to_string(),
+                                ..SecretProjection::default()
+                            }),
+                            ..VolumeProjection::default()
+                        }]),
+                        ..ProjectedVolumeSource::default()
+                    }),
+                    ..Volume::default()
+                }]),
+                ..PodSpec::default()
+            }),
+            ..Pod::default()
+        }];
+        let mut pod_spec_referrers = Vec::new();
+        extend_with_referrers(&mut pod_spec_referrers, &pods);
+        let referrers = Referrers {
+            namespace: "default",
+            ingresses: &[],
+            gateways: &[],
+            service_accounts: &[],
+        };
+        let index = index_references(&pod_spec_referrers, &referrers);
+
+        let release_secret = "sh.helm.release.v1.app.v1";
+        let explanations = explain(
+            ObjectKind::Secret,
+            [
+                (release_secret, Some("Helm release")),
+                ("credentials", None),
+            ]
+            .into_iter(),
+            &index,
+        );
+        let paths: Vec<&str> = explanations[0]
+            .references
+            .iter()
+            .map(|reference| reference.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "spec.initContainers[0].env[0].valueFrom.secretKeyRef",
+                "spec.volumes[0].projected.sources[0].secret",
+            ]
+        );
+        assert!(explanations[1].references.is_empty());
+        assert_eq!(explanations[1].rule, Some("Helm release"));
+    }
+}
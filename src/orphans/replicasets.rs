@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use k8s_openapi::api::apps::v1::{Deployment, ReplicaSet};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use serde::Serialize;
+
+const REVISION_ANNOTATION: &str = "deployment.kubernetes.io/revision";
+
+/// `ReplicaSet`s scaled to zero that are no longer useful for a rollback, together with `ConfigMap`s
+/// and `Secret`s that would become orphans if these `ReplicaSet`s were removed.
+#[derive(Serialize)]
+pub struct StaleReplicaSets {
+    pub deployments: Vec<StaleRevisions>,
+    pub configmaps: HashSet<String>,
+    pub secrets: HashMap<String, String>,
+}
+
+/// Stale `ReplicaSet`s owned by a single `Deployment`, newest revision first.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct StaleRevisions {
+    pub deployment: String,
+    pub deployment_exists: bool,
+    pub replicasets: Vec<String>,
+}
+
+/// Groups `ReplicaSet`s scaled to zero by the UID of their owning `Deployment`. When the `Deployment`
+/// is gone, including when re-created under the same name, all of them are stale. Otherwise, the `revision_history` newest revisions besides the current one
+/// are kept for rollbacks and only the older ones are stale.
+pub fn find_stale_replicasets(
+    replicasets: &[ReplicaSet],
+    deployments: &[Deployment],
+    revision_history: usize,
+) -> Vec<StaleRevisions> {
+    let mut by_owner: BTreeMap<(&str, &str), Vec<&ReplicaSet>> = BTreeMap::new();
+    replicasets
+        .iter()
+        .filter(|rs| rs.spec.as_ref().and_then(|spec| spec.replicas) == Some(0))
+        .for_each(|rs| {
+            let owner = rs
+                .metadata
+                .owner_references
+                .iter()
+                .flatten()
+                .find(|owner| owner.kind == "Deployment");
+            if let Some(owner) = owner {
+                let key = (owner.name.as_str(), owner.uid.as_str());
+                by_owner.entry(key).or_default().push(rs);
+            }
+        });
+
+    by_owner
+        .into_iter()
+        .filter_map(|((deployment_name, deployment_uid), mut owned)| {
+            owned.sort_by_key(|rs| std::cmp::Reverse(revision(&rs.metadata)));
+            let deployment = deployments
+                .iter()
+                .find(|deployment| deployment.metadata.uid.as_deref() == Some(deployment_uid));
+
+            let stale: Vec<&&ReplicaSet> = match deployment {
+                None => owned.iter().collect(),
+                Some(deployment) => {
+                    let current_revision = revision(&deployment.metadata);
+                    owned
+                        .iter()
+                        .filter(|rs| revision(&rs.metadata) != current_revision)
+                        .skip(revision_history)
+                        .collect()
+                }
+            };
+            let stale: Vec<String> = stale
+                .into_iter()
+                .filter_map(|rs| rs.metadata.name.clone())
+                .collect();
+
+            (!stale.is_empty()).then(|| StaleRevisions {
+                deployment: deployment_name.to_string(),
+                deployment_exists: deployment.is_some(),
+                replicasets: stale,
+            })
+        })
+        .collect()
+}
+
+fn revision(metadata: &ObjectMeta) -> Option<i64> {
+    metadata
+        .annotations
+        .as_ref()?
+        .get(REVISION_ANNOTATION)?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::api::apps::v1::{Deployment, ReplicaSet, ReplicaSetSpec};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::replicasets::{find_stale_replicasets, StaleRevisions};
+
+    fn metadata(name: &str, revision: u32) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            uid: Some(format!("{name}-uid")),
+            annotations: Some(BTreeMap::from([(
+                "deployment.kubernetes.io/revision".to_string(),
+                revision.to_string(),
+            )])),
+            ..ObjectMeta::default()
+        }
+    }
+
+    fn replicaset(owner: &str, owner_uid: &str, revision: u32, replicas: i32) -> ReplicaSet {
+        let mut metadata = metadata(&format!("{owner}-{revision}"), revision);
+        metadata.owner_references = Some(vec![OwnerReference {
+            kind: "Deployment".to_string(),
+            name: owner.to_string(),
+            uid: owner_uid.to_string(),
+            ..OwnerReference::default()
+        }]);
+        ReplicaSet {
+            metadata,
+            spec: Some(ReplicaSetSpec {
+                replicas: Some(replicas),
+                ..ReplicaSetSpec::default()
+            }),
+            ..ReplicaSet::default()
+        }
+    }
+
+    #[test]
+    fn revisions_beyond_history_and_missing_owner() {
+        let deployments = vec![Deployment {
+            metadata: metadata("app", 4),
+            ..Deployment::default()
+        }];
+        let replicasets = vec![
+            replicaset("app", "app-uid", 1, 0),
+            replicaset("app", "app-uid", 2, 0),
+            replicaset("app", "app-uid", 3, 0),
+            replicaset("app", "app-uid", 4, 2),
+            replicaset("removed", "removed-uid", 1, 0),
+            // Left behind by a deleted Deployment of the same name.
+            replicaset("app", "app-old-uid", 0, 0),
+        ];
+
+        assert_eq!(
+            find_stale_replicasets(&replicasets, &deployments, 1),
+            vec![
+                StaleRevisions {
+                    deployment: "app".to_string(),
+                    deployment_exists: false,
+                    replicasets: vec!["app-0".to_string()],
+                },
+                StaleRevisions {
+                    deployment: "app".to_string(),
+                    deployment_exists: true,
+                    replicasets: vec!["app-2".to_string(), "app-1".to_string()],
+                },
+                StaleRevisions {
+                    deployment: "removed".to_string(),
+                    deployment_exists: false,
+                    replicasets: vec!["removed-1".to_string()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::api::policy::v1::PodDisruptionBudget;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::api::{ApiResource, DynamicObject, GroupVersionKind};
+
+use crate::selector::selector_matches;
+
+/// Kinds of workloads listed by `find_orphans`. Targets of any other kind, e.g. custom resources,
+/// can't be verified and are never reported.
+pub const KNOWN_WORKLOAD_KINDS: [&str; 7] = [
+    "Deployment",
+    "ReplicaSet",
+    "StatefulSet",
+    "DaemonSet",
+    "Job",
+    "CronJob",
+    "ReplicationController",
+];
+
+/// Kind and name pairs of all existing workloads.
+pub type WorkloadKeys<'a> = HashSet<(&'static str, &'a str)>;
+
+pub fn vpa_api_resource() -> ApiResource {
+    ApiResource::from_gvk_with_plural(
+        &GroupVersionKind::gvk("autoscaling.k8s.io", "v1", "VerticalPodAutoscaler"),
+        "verticalpodautoscalers",
+    )
+}
+
+pub fn extend_with_keys<'a, T>(workload_keys: &mut WorkloadKeys<'a>, workloads: &'a [T])
+where
+    T: k8s_openapi::Resource + k8s_openapi::Metadata<Ty = ObjectMeta>,
+{
+    let keys = workloads
+        .iter()
+        .filter_map(|workload| workload.metadata().name.as_deref())
+        .map(|name| (T::KIND, name));
+    workload_keys.extend(keys);
+}
+
+/// Finds `HorizontalPodAutoscaler`s with a `scaleTargetRef` pointing to a missing workload.
+pub fn find_orphan_hpas(
+    hpas: &[HorizontalPodAutoscaler],
+    workload_keys: &WorkloadKeys,
+) -> HashSet<String> {
+    hpas.iter()
+        .filter(|hpa| {
+            hpa.spec.as_ref().is_some_and(|spec| {
+                is_missing_workload(
+                    &spec.scale_target_ref.kind,
+                    &spec.scale_target_ref.name,
+                    workload_keys,
+                )
+            })
+        })
+        .filter_map(|hpa| hpa.metadata.name.clone())
+        .collect()
+}
+
+/// Finds `VerticalPodAutoscaler`s with a `targetRef` pointing to a missing workload.
+pub fn find_orphan_vpas(vpas: &[DynamicObject], workload_keys: &WorkloadKeys) -> HashSet<String> {
+    vpas.iter()
+        .filter(|vpa| {
+            let target_ref = &vpa.data["spec"]["targetRef"];
+            match (target_ref["kind"].as_str(), target_ref["name"].as_str()) {
+                (Some(kind), Some(name)) => is_missing_workload(kind, name, workload_keys),
+                _ => false,
+            }
+        })
+        .filter_map(|vpa| vpa.metadata.name.clone())
+        .collect()
+}
+
+/// Finds `PodDisruptionBudget`s whose selector matches none of the `pods`. A PDB without
+/// a selector matches no pods at all.
+pub fn find_orphan_pdbs(pdbs: &[PodDisruptionBudget], pods: &[Pod]) -> HashSet<String> {
+    pdbs.iter()
+        .filter(
+            |pdb| match pdb.spec.as_ref().and_then(|spec| spec.selector.as_ref()) {
+                None => true,
+                Some(selector) => !pods
+                    .iter()
+                    .any(|pod| selector_matches(selector, pod.metadata.labels.as_ref())),
+            },
+        )
+        .filter_map(|pdb| pdb.metadata.name.clone())
+        .collect()
+}
+
+fn is_missing_workload(kind: &str, name: &str, workload_keys: &WorkloadKeys) -> bool {
+    KNOWN_WORKLOAD_KINDS.contains(&kind) && !workload_keys.contains(&(kind, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+
+    use k8s_openapi::api::apps::v1::Deployment;
+    use k8s_openapi::api::autoscaling::v2::{
+        CrossVersionObjectReference, HorizontalPodAutoscaler, HorizontalPodAutoscalerSpec,
+    };
+    use k8s_openapi::api::core::v1::Pod;
+    use k8s_openapi::api::policy::v1::{PodDisruptionBudget, PodDisruptionBudgetSpec};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+    use kube::api::{DynamicObject, ObjectMeta};
+    use serde_json::json;
+
+    use crate::orphans::scaling::{
+        extend_with_keys, find_orphan_hpas, find_orphan_pdbs, find_orphan_vpas, vpa_api_resource,
+        WorkloadKeys,
+    };
+
+    fn metadata(name: &str, app: Option<&str>) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            labels: app.map(|app| BTreeMap::from([("app".to_string(), app.to_string())])),
+            ..ObjectMeta::default()
+        }
+    }
+
+    fn hpa(name: &str, kind: &str, target: &str) -> HorizontalPodAutoscaler {
+        HorizontalPodAutoscaler {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            spec: Some(HorizontalPodAutoscalerSpec {
+                scale_target_ref: CrossVersionObjectReference {
+                    kind: kind.to_string(),
+                    name: target.to_string(),
+                    ..CrossVersionObjectReference::default()
+                },
+                max_replicas: 3,
+                ..HorizontalPodAutoscalerSpec::default()
+            }),
+            ..HorizontalPodAutoscaler::default()
+        }
+    }
+
+    #[test]
+    fn hpa_targeting_missing_deployment() {
+        let deployments = vec![Deployment {
+            metadata: ObjectMeta {
+                name: Some("existing".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..Deployment::default()
+        }];
+        let mut workload_keys = WorkloadKeys::new();
+        extend_with_keys(&mut workload_keys, &deployments);
+
+        let hpas = vec![
+            hpa("used-hpa", "Deployment", "existing"),
+            hpa("orphan-hpa", "Deployment", "removed"),
+            hpa("custom-target-hpa", "Rollout", "removed"),
+        ];
+        let orphans = find_orphan_hpas(&hpas, &workload_keys);
+
+        assert_eq!(orphans.len(), 1);
+        assert!(orphans.contains("orphan-hpa"));
+    }
+
+    #[test]
+    fn vpa_targeting_missing_deployment() {
+        let deployments = vec![Deployment {
+            metadata: metadata("existing", None),
+            ..Deployment::default()
+        }];
+        let mut workload_keys = WorkloadKeys::new();
+        extend_with_keys(&mut workload_keys, &deployments);
+
+        let vpa = |name: &str, kind: &str, target: &str| {
+            DynamicObject::new(name, &vpa_api_resource()).data(json!({
+                "spec": {"targetRef": {"apiVersion": "apps/v1", "kind": kind, "name": target}}
+            }))
+        };
+        let vpas = vec![
+            vpa("used-vpa", "Deployment", "existing"),
+            vpa("orphan-vpa", "Deployment", "removed"),
+            vpa("custom-target-vpa", "Rollout", "removed"),
+        ];
+
+        assert_eq!(
+            find_orphan_vpas(&vpas, &workload_keys),
+            HashSet::from(["orphan-vpa".to_string()])
+        );
+    }
+
+    #[test]
+    fn pdbs_matching_no_pods() {
+        let pdb = |name: &str, app: Option<&str>| PodDisruptionBudget {
+            metadata: metadata(name, None),
+            spec: Some(PodDisruptionBudgetSpec {
+                selector: app.map(|app| LabelSelector {
+                    match_labels: Some(BTreeMap::from([("app".to_string(), app.to_string())])),
+                    ..LabelSelector::default()
+                }),
+                ..PodDisruptionBudgetSpec::default()
+            }),
+            ..PodDisruptionBudget::default()
+        };
+        let pods = vec![Pod {
+            metadata: metadata("web-x2x7q", Some("web")),
+            ..Pod::default()
+        }];
+        let pdbs = vec![
+            pdb("web", Some("web")),
+            pdb("removed", Some("removed")),
+            pdb("without-selector", None),
+        ];
+
+        assert_eq!(
+            find_orphan_pdbs(&pdbs, &pods),
+            HashSet::from(["removed".to_string(), "without-selector".to_string()])
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::chrono::{DateTime, Utc};
+use kube::api::{ApiResource, GroupVersionKind};
+
+pub const OPAQUE: &str = "Opaque";
+const DOCKER_CONFIG_JSON: &str = "kubernetes.io/dockerconfigjson";
+const DOCKER_CONFIG: &str = "kubernetes.io/dockercfg";
+const BOOTSTRAP_TOKEN: &str = "bootstrap.kubernetes.io/token";
+const BOOTSTRAP_TOKEN_EXPIRATION: &str = "expiration";
+
+/// Type of the `secret`, `Opaque` when not set.
+pub fn secret_type(secret: &Secret) -> &str {
+    secret.type_.as_deref().unwrap_or(OPAQUE)
+}
+
+/// Docker config secrets are only used when referenced as `imagePullSecrets`.
+pub fn is_pull_secret(secret_type: &str) -> bool {
+    secret_type == DOCKER_CONFIG_JSON || secret_type == DOCKER_CONFIG
+}
+
+/// Bootstrap tokens are used by the cluster itself when joining nodes, until they expire.
+pub fn is_unexpired_bootstrap_token(secret: &Secret, now: DateTime<Utc>) -> bool {
+    if secret_type(secret) != BOOTSTRAP_TOKEN {
+        return false;
+    }
+    let expiration = secret
+        .data
+        .as_ref()
+        .and_then(|data| data.get(BOOTSTRAP_TOKEN_EXPIRATION))
+        .and_then(|expiration| std::str::from_utf8(&expiration.0).ok())
+        .and_then(|expiration| DateTime::parse_from_rfc3339(expiration).ok());
+    expiration.is_none_or(|expiration| expiration > now)
+}
+
+/// Checks whether the `secret_type` matches the user-provided `filter`. Besides the full type,
+/// e.g. `kubernetes.io/tls`, the part after the last slash, e.g. `tls`, is accepted. Case is ignored.
+pub fn matches_type(secret_type: &str, filter: &str) -> bool {
+    let short_type = secret_type.rsplit('/').next().unwrap_or(secret_type);
+    secret_type.eq_ignore_ascii_case(filter) || short_type.eq_ignore_ascii_case(filter)
+}
+
+pub fn gateway_api_resource() -> ApiResource {
+    ApiResource::from_gvk_with_plural(
+        &GroupVersionKind::gvk("gateway.networking.k8s.io", "v1", "Gateway"),
+        "gateways",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::orphans::secret_types::matches_type;
+
+    #[test]
+    fn type_filter_by_full_or_short_name() {
+        assert!(matches_type("kubernetes.io/tls", "tls"));
+        assert!(matches_type("kubernetes.io/tls", "kubernetes.io/tls"));
+        assert!(matches_type("Opaque", "opaque"));
+        assert!(!matches_type("kubernetes.io/dockerconfigjson", "tls"));
+    }
+}
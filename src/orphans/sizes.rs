@@ -0,0 +1,208 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+
+use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::ByteString;
+use serde::Serialize;
+
+/// Maximum size of a `ConfigMap` or `Secret`, enforced by the API server and etcd.
+const SIZE_LIMIT: usize = 1024 * 1024;
+const LARGEST_KEYS: usize = 3;
+
+/// A `ConfigMap` or `Secret` close to the size limit, with its largest keys.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ObjectSize {
+    pub name: String,
+    pub size: usize,
+    pub percent_of_limit: usize,
+    pub largest_keys: Vec<KeySize>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct KeySize {
+    pub key: String,
+    pub size: usize,
+}
+
+/// Sizes of all orphan `ConfigMap`s and `Secret`s, i.e. space freed by removing them.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Footprint {
+    pub configmaps: usize,
+    pub secrets: usize,
+    pub total: usize,
+}
+
+#[derive(Serialize)]
+pub struct Sizes {
+    pub configmaps: Vec<ObjectSize>,
+    pub secrets: Vec<ObjectSize>,
+    pub orphans_footprint: Footprint,
+}
+
+/// Size of the object encoded as JSON, including metadata with annotations such as
+/// `kubectl.kubernetes.io/last-applied-configuration`, but without `managedFields` maintained
+/// by the API server.
+pub fn encoded_size<T: Serialize>(object: &T) -> usize {
+    let Ok(mut encoded) = serde_json::to_value(object) else {
+        return 0;
+    };
+    if let Some(metadata) = encoded["metadata"].as_object_mut() {
+        metadata.remove("managedFields");
+    }
+    serde_json::to_vec(&encoded).map_or(0, |encoded| encoded.len())
+}
+
+/// Finds `ConfigMap`s taking at least `threshold` percent of the size limit, largest first.
+pub fn find_large_configmaps(cfgmaps: &[ConfigMap], threshold: u8) -> Vec<ObjectSize> {
+    let sizes = cfgmaps.iter().map(|cfgmap| {
+        let data = cfgmap
+            .data
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key, value.len()));
+        let binary_data = cfgmap
+            .binary_data
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key, base64_len(value)));
+        (
+            &cfgmap.metadata,
+            encoded_size(cfgmap),
+            data.chain(binary_data).collect(),
+        )
+    });
+    find_large_objects(sizes, threshold)
+}
+
+/// Finds `Secret`s taking at least `threshold` percent of the size limit, largest first.
+pub fn find_large_secrets(secrets: &[Secret], threshold: u8) -> Vec<ObjectSize> {
+    let sizes = secrets.iter().map(|secret| {
+        let data = secret
+            .data
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key, base64_len(value)));
+        (&secret.metadata, encoded_size(secret), data.collect())
+    });
+    find_large_objects(sizes, threshold)
+}
+
+/// Sums encoded sizes of `objects` whose name is in `orphans`.
+pub fn sum_sizes<'a, T: Serialize + 'a>(
+    objects: impl IntoIterator<Item = &'a T>,
+    name: impl Fn(&T) -> Option<&str>,
+    orphans: &HashSet<&str>,
+) -> usize {
+    objects
+        .into_iter()
+        .filter(|object| name(object).is_some_and(|name| orphans.contains(name)))
+        .map(encoded_size)
+        .sum()
+}
+
+fn find_large_objects<'a>(
+    sizes: impl Iterator<Item = (&'a ObjectMeta, usize, BTreeMap<&'a String, usize>)>,
+    threshold: u8,
+) -> Vec<ObjectSize> {
+    let mut large_objects: Vec<ObjectSize> = sizes
+        .filter(|(_, size, _)| size * 100 >= SIZE_LIMIT * threshold as usize)
+        .filter_map(|(metadata, size, keys)| {
+            let mut largest_keys: Vec<KeySize> = keys
+                .into_iter()
+                .map(|(key, size)| KeySize {
+                    key: key.clone(),
+                    size,
+                })
+                .collect();
+            largest_keys.sort_by_key(|key| Reverse(key.size));
+            largest_keys.truncate(LARGEST_KEYS);
+
+            Some(ObjectSize {
+                name: metadata.name.clone()?,
+                size,
+                percent_of_limit: size * 100 / SIZE_LIMIT,
+                largest_keys,
+            })
+        })
+        .collect();
+    large_objects.sort_by_key(|object| Reverse(object.size));
+    large_objects
+}
+
+/// Binary values are base64-encoded by the API.
+fn base64_len(value: &ByteString) -> usize {
+    value.0.len().div_ceil(3) * 4
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ManagedFieldsEntry;
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::sizes::{encoded_size, find_large_configmaps, sum_sizes, KeySize};
+
+    #[test]
+    fn configmap_close_to_limit() {
+        let cfgmap = |name: &str, size: usize| ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            data: Some(BTreeMap::from([
+                ("large".to_string(), "x".repeat(size)),
+                ("small".to_string(), "x".to_string()),
+            ])),
+            ..ConfigMap::default()
+        };
+        let cfgmaps = vec![cfgmap("huge", 900 * 1024), cfgmap("tiny", 1024)];
+
+        let large_cfgmaps = find_large_configmaps(&cfgmaps, 80);
+
+        assert_eq!(large_cfgmaps.len(), 1);
+        assert_eq!(large_cfgmaps[0].name, "huge");
+        assert_eq!(large_cfgmaps[0].percent_of_limit, 87);
+        assert_eq!(
+            large_cfgmaps[0].largest_keys,
+            vec![
+                KeySize {
+                    key: "large".to_string(),
+                    size: 900 * 1024,
+                },
+                KeySize {
+                    key: "small".to_string(),
+                    size: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn orphans_sum_without_managed_fields() {
+        let cfgmap = |name: &str| ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            data: Some(BTreeMap::from([("key".to_string(), "value".to_string())])),
+            ..ConfigMap::default()
+        };
+        let mut managed = cfgmap("managed");
+        managed.metadata.managed_fields = Some(vec![ManagedFieldsEntry {
+            manager: Some("kubectl".repeat(100)),
+            ..ManagedFieldsEntry::default()
+        }]);
+        let unmanaged = cfgmap("managed");
+        assert_eq!(encoded_size(&managed), encoded_size(&unmanaged));
+
+        let cfgmaps = vec![managed, cfgmap("used"), cfgmap("orphan")];
+        let orphans = HashSet::from(["managed", "orphan"]);
+        assert_eq!(
+            sum_sizes(&cfgmaps, |cfgmap| cfgmap.metadata.name.as_deref(), &orphans),
+            encoded_size(&unmanaged) + encoded_size(&cfgmaps[2])
+        );
+    }
+}
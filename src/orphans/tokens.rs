@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use k8s_openapi::api::core::v1::{Secret, ServiceAccount};
+use serde::Serialize;
+
+const SERVICE_ACCOUNT_TOKEN_TYPE: &str = "kubernetes.io/service-account-token";
+pub(crate) const SERVICE_ACCOUNT_NAME_ANNOTATION: &str = "kubernetes.io/service-account.name";
+const SERVICE_ACCOUNT_UID_ANNOTATION: &str = "kubernetes.io/service-account.uid";
+const HIGH_SEVERITY: &str = "high";
+
+/// A `kubernetes.io/service-account-token` `Secret` of a deleted `ServiceAccount`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct OrphanToken {
+    pub name: String,
+    pub service_account: String,
+    pub reason: String,
+    pub severity: &'static str,
+}
+
+/// Whether the `secret` is a long-lived token of a `ServiceAccount`.
+pub fn is_service_account_token(secret: &Secret) -> bool {
+    secret.type_.as_deref() == Some(SERVICE_ACCOUNT_TOKEN_TYPE)
+}
+
+/// Finds service account token `Secret`s whose `ServiceAccount` no longer exists, or was re-created
+/// with a different UID.
+pub fn find_orphan_tokens(
+    secrets: &[Secret],
+    service_accounts: &[ServiceAccount],
+) -> Vec<OrphanToken> {
+    let service_account_uids: HashMap<&str, Option<&str>> = service_accounts
+        .iter()
+        .filter_map(|sa| Some((sa.metadata.name.as_deref()?, sa.metadata.uid.as_deref())))
+        .collect();
+
+    let mut orphan_tokens: Vec<OrphanToken> = secrets
+        .iter()
+        .filter(|secret| is_service_account_token(secret))
+        .filter_map(|secret| {
+            let annotations = secret.metadata.annotations.as_ref();
+            let service_account = annotations?.get(SERVICE_ACCOUNT_NAME_ANNOTATION)?;
+            let token_uid = annotations?.get(SERVICE_ACCOUNT_UID_ANNOTATION);
+
+            let reason = match service_account_uids.get(service_account.as_str()) {
+                None => "ServiceAccount does not exist".to_string(),
+                Some(Some(uid)) if token_uid.is_some_and(|token_uid| token_uid != uid) => {
+                    format!("ServiceAccount UID is '{uid}', token was issued for a deleted one")
+                }
+                Some(_) => return None,
+            };
+
+            Some(OrphanToken {
+                name: secret.metadata.name.clone()?,
+                service_account: service_account.clone(),
+                reason,
+                severity: HIGH_SEVERITY,
+            })
+        })
+        .collect();
+    orphan_tokens.sort_by(|a, b| a.name.cmp(&b.name));
+    orphan_tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::api::core::v1::{Secret, ServiceAccount};
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::tokens::find_orphan_tokens;
+
+    fn token(name: &str, service_account: &str, uid: &str) -> Secret {
+        Secret {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                annotations: Some(BTreeMap::from([
+                    (
+                        "kubernetes.io/service-account.name".to_string(),
+                        service_account.to_string(),
+                    ),
+                    (
+                        "kubernetes.io/service-account.uid".to_string(),
+                        uid.to_string(),
+                    ),
+                ])),
+                ..ObjectMeta::default()
+            },
+            type_: Some("kubernetes.io/service-account-token".to_string()),
+            ..Secret::default()
+        }
+    }
+
+    #[test]
+    fn tokens_of_deleted_service_accounts() {
+        let service_accounts = vec![ServiceAccount {
+            metadata: ObjectMeta {
+                name: Some("builder".to_string()),
+                uid: Some("uid-2".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ServiceAccount::default()
+        }];
+        let secrets = vec![
+            token("builder-token", "builder", "uid-2"),
+            token("builder-token-old", "builder", "uid-1"),
+            token("deployer-token", "deployer", "uid-3"),
+        ];
+
+        let orphan_tokens: Vec<String> = find_orphan_tokens(&secrets, &service_accounts)
+            .into_iter()
+            .map(|token| token.name)
+            .collect();
+        assert_eq!(orphan_tokens, vec!["builder-token-old", "deployer-token"]);
+    }
+}
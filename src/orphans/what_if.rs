@@ -0,0 +1,437 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use k8s_openapi::api::core::v1::PersistentVolumeClaim;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::chrono::{DateTime, Utc};
+use kube::{Client, Resource};
+use serde::Serialize;
+
+use crate::orphans::references::{owner_uids, PodSpecReferrer};
+use crate::orphans::secret_types::secret_type;
+use crate::orphans::{detect_orphans, list_namespace_resources, NamespaceResources, OrphanOptions};
+use crate::resources::list_resource;
+use crate::workloads::WorkloadRef;
+
+/// Finds `ConfigMap`s, `Secret`s and `PersistentVolumeClaim`s that would become orphans once the
+/// `targets` and all resources they own are deleted.
+pub async fn what_if_delete(
+    client: &Client,
+    namespace: &str,
+    targets: &[WorkloadRef],
+) -> Result<Impact> {
+    let (resources, persistent_volume_claims) =
+        tokio::try_join!(list_namespace_resources(client, namespace), async {
+            Ok(list_resource::<PersistentVolumeClaim>(client, namespace).await?)
+        })?;
+
+    let mut objects = namespaced_metadata(&resources);
+    objects.extend(persistent_volume_claims.iter().map(|claim| &claim.metadata));
+    let deleted = find_deleted(&resources.pod_spec_referrers(), &objects, targets)?;
+    Ok(find_impact(
+        namespace,
+        &resources,
+        &persistent_volume_claims,
+        &deleted,
+        Utc::now(),
+    ))
+}
+
+/// Metadata of all namespaced `resources`, i.e. objects the garbage collector may delete.
+fn namespaced_metadata(resources: &NamespaceResources) -> Vec<&ObjectMeta> {
+    let mut objects = Vec::new();
+    extend_with_metadata(&mut objects, &resources.cfgmaps);
+    extend_with_metadata(&mut objects, &resources.secrets);
+    extend_with_metadata(&mut objects, &resources.deployments);
+    extend_with_metadata(&mut objects, &resources.replicasets);
+    extend_with_metadata(&mut objects, &resources.statefulsets);
+    extend_with_metadata(&mut objects, &resources.daemonsets);
+    extend_with_metadata(&mut objects, &resources.jobs);
+    extend_with_metadata(&mut objects, &resources.cronjobs);
+    extend_with_metadata(&mut objects, &resources.replication_controllers);
+    extend_with_metadata(&mut objects, &resources.pods);
+    extend_with_metadata(&mut objects, &resources.ingresses);
+    extend_with_metadata(&mut objects, &resources.service_accounts);
+    extend_with_metadata(&mut objects, resources.hpas.as_deref().unwrap_or_default());
+    extend_with_metadata(&mut objects, resources.vpas.as_deref().unwrap_or_default());
+    extend_with_metadata(&mut objects, resources.pdbs.as_deref().unwrap_or_default());
+    extend_with_metadata(
+        &mut objects,
+        resources.network_policies.as_deref().unwrap_or_default(),
+    );
+    extend_with_metadata(&mut objects, resources.roles.as_deref().unwrap_or_default());
+    extend_with_metadata(
+        &mut objects,
+        resources.role_bindings.as_deref().unwrap_or_default(),
+    );
+    extend_with_metadata(
+        &mut objects,
+        resources.gateways.as_deref().unwrap_or_default(),
+    );
+    objects
+}
+
+fn extend_with_metadata<'a, T: Resource>(objects: &mut Vec<&'a ObjectMeta>, resources: &'a [T]) {
+    objects.extend(resources.iter().map(Resource::meta));
+}
+
+/// UIDs of the `targets` and, transitively, of all `objects` owned by them. As with the garbage
+/// collector, an object is only deleted once all of its owners are.
+fn find_deleted<'a>(
+    workloads: &[PodSpecReferrer<'a>],
+    objects: &[&'a ObjectMeta],
+    targets: &[WorkloadRef],
+) -> Result<HashSet<&'a str>> {
+    let mut deleted = HashSet::new();
+    for target in targets {
+        let workload = workloads
+            .iter()
+            .find(|workload| {
+                workload.kind == target.kind
+                    && workload.metadata.name.as_deref() == Some(&target.name)
+            })
+            .ok_or_else(|| anyhow!("{target} not found"))?;
+        deleted.extend(workload.metadata.uid.as_deref());
+    }
+
+    loop {
+        let owned: Vec<&str> = objects
+            .iter()
+            .filter(|object| {
+                let mut owners = owner_uids(object).peekable();
+                owners.peek().is_some() && owners.all(|owner| deleted.contains(owner))
+            })
+            .filter_map(|object| object.uid.as_deref())
+            .filter(|uid| !deleted.contains(uid))
+            .collect();
+        if owned.is_empty() {
+            return Ok(deleted);
+        }
+        deleted.extend(owned);
+    }
+}
+
+/// Runs the orphan detection with and without the `deleted` objects, keeping only objects orphaned
+/// by the deletion. Objects deleted themselves, e.g. owned by a deleted workload, are not reported.
+fn find_impact(
+    namespace: &str,
+    resources: &NamespaceResources,
+    persistent_volume_claims: &[PersistentVolumeClaim],
+    deleted: &HashSet<&str>,
+    now: DateTime<Utc>,
+) -> Impact {
+    let remaining = without_deleted(resources, deleted);
+    let options = OrphanOptions::default();
+    let orphans_before = detect_orphans(namespace, resources, &options, now);
+    let orphans_after = detect_orphans(namespace, &remaining, &options, now);
+
+    let cfgmaps_before = orphans_before.configmap_names();
+    let secrets_before = orphans_before.secret_names();
+    let configmaps = orphans_after
+        .configmap_names()
+        .into_iter()
+        .filter(|cfgmap| !cfgmaps_before.contains(cfgmap))
+        .map(str::to_string)
+        .collect();
+    let secrets_after = orphans_after.secret_names();
+    let secrets = remaining
+        .secrets
+        .iter()
+        .filter_map(|secret| Some((secret.metadata.name.as_deref()?, secret_type(secret))))
+        .filter(|(name, _)| secrets_after.contains(name) && !secrets_before.contains(name))
+        .map(|(name, secret_type)| (name.to_string(), secret_type.to_string()))
+        .collect();
+
+    let claims_before = claim_names(&resources.pod_spec_referrers());
+    let claims_after = claim_names(&remaining.pod_spec_referrers());
+    let orphan_claims = without_deleted_objects(persistent_volume_claims, deleted)
+        .into_iter()
+        .filter_map(|claim| claim.metadata.name)
+        .filter(|claim| {
+            claims_before.contains(claim.as_str()) && !claims_after.contains(claim.as_str())
+        })
+        .collect();
+
+    Impact {
+        configmaps,
+        secrets,
+        persistent_volume_claims: orphan_claims,
+    }
+}
+
+/// Copy of the `resources` without `deleted` objects.
+fn without_deleted(resources: &NamespaceResources, deleted: &HashSet<&str>) -> NamespaceResources {
+    NamespaceResources {
+        cfgmaps: without_deleted_objects(&resources.cfgmaps, deleted),
+        secrets: without_deleted_objects(&resources.secrets, deleted),
+        deployments: without_deleted_objects(&resources.deployments, deleted),
+        replicasets: without_deleted_objects(&resources.replicasets, deleted),
+        statefulsets: without_deleted_objects(&resources.statefulsets, deleted),
+        daemonsets: without_deleted_objects(&resources.daemonsets, deleted),
+        jobs: without_deleted_objects(&resources.jobs, deleted),
+        cronjobs: without_deleted_objects(&resources.cronjobs, deleted),
+        replication_controllers: without_deleted_objects(
+            &resources.replication_controllers,
+            deleted,
+        ),
+        pods: without_deleted_objects(&resources.pods, deleted),
+        ingresses: without_deleted_objects(&resources.ingresses, deleted),
+        service_accounts: without_deleted_objects(&resources.service_accounts, deleted),
+        hpas: without_deleted_optional(&resources.hpas, deleted),
+        vpas: without_deleted_optional(&resources.vpas, deleted),
+        pdbs: without_deleted_optional(&resources.pdbs, deleted),
+        network_policies: without_deleted_optional(&resources.network_policies, deleted),
+        roles: without_deleted_optional(&resources.roles, deleted),
+        role_bindings: without_deleted_optional(&resources.role_bindings, deleted),
+        cluster_roles: resources.cluster_roles.clone(),
+        cluster_role_bindings: resources.cluster_role_bindings.clone(),
+        gateways: without_deleted_optional(&resources.gateways, deleted),
+        namespaces: resources.namespaces.clone(),
+    }
+}
+
+fn without_deleted_optional<T: Resource + Clone>(
+    objects: &Option<Vec<T>>,
+    deleted: &HashSet<&str>,
+) -> Option<Vec<T>> {
+    objects
+        .as_deref()
+        .map(|objects| without_deleted_objects(objects, deleted))
+}
+
+fn without_deleted_objects<T: Resource + Clone>(objects: &[T], deleted: &HashSet<&str>) -> Vec<T> {
+    objects
+        .iter()
+        .filter(|object| {
+            object
+                .meta()
+                .uid
+                .as_deref()
+                .is_none_or(|uid| !deleted.contains(uid))
+        })
+        .cloned()
+        .collect()
+}
+
+fn claim_names<'a>(workloads: &[PodSpecReferrer<'a>]) -> HashSet<&'a str> {
+    workloads
+        .iter()
+        .flat_map(|workload| workload.pod_spec.volumes.iter().flatten())
+        .filter_map(|volume| volume.persistent_volume_claim.as_ref())
+        .map(|claim| claim.claim_name.as_str())
+        .collect()
+}
+
+/// Objects newly orphaned by a deletion.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Impact {
+    pub configmaps: HashSet<String>,
+    /// Names of `Secret`s with their type.
+    pub secrets: HashMap<String, String>,
+    #[serde(rename = "persistentvolumeclaims")]
+    pub persistent_volume_claims: HashSet<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use k8s_openapi::api::apps::v1::{Deployment, DeploymentSpec, ReplicaSet, ReplicaSetSpec};
+    use k8s_openapi::api::core::v1::{
+        ConfigMap, ConfigMapVolumeSource, PersistentVolumeClaim, PersistentVolumeClaimVolumeSource,
+        Pod, PodSpec, PodTemplateSpec, Secret, SecretVolumeSource, Volume,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+    use k8s_openapi::chrono::Utc;
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::what_if::{find_deleted, find_impact, namespaced_metadata, Impact};
+    use crate::orphans::NamespaceResources;
+    use crate::workloads::{WorkloadKind, WorkloadRef};
+
+    fn metadata(name: &str, owner_uid: Option<&str>) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            uid: Some(format!("{name}-uid")),
+            owner_references: owner_uid.map(|uid| {
+                vec![OwnerReference {
+                    uid: uid.to_string(),
+                    ..OwnerReference::default()
+                }]
+            }),
+            ..ObjectMeta::default()
+        }
+    }
+
+    fn pod_spec(cfgmap: &str, secret: &str, claim: &str) -> PodSpec {
+        PodSpec {
+            volumes: Some(vec![
+                Volume {
+                    name: "config".to_string(),
+                    config_map: Some(ConfigMapVolumeSource {
+                        name: cfgmap.to_string(),
+                        ..ConfigMapVolumeSource::default()
+                    }),
+                    ..Volume::default()
+                },
+                Volume {
+                    name: "secret".to_string(),
+                    secret: Some(SecretVolumeSource {
+                        secret_name: Some(secret.to_string()),
+                        ..SecretVolumeSource::default()
+                    }),
+                    ..Volume::default()
+                },
+                Volume {
+                    name: "data".to_string(),
+                    persistent_volume_claim: Some(PersistentVolumeClaimVolumeSource {
+                        claim_name: claim.to_string(),
+                        ..PersistentVolumeClaimVolumeSource::default()
+                    }),
+                    ..Volume::default()
+                },
+            ]),
+            ..PodSpec::default()
+        }
+    }
+
+    #[test]
+    fn deleting_deployment_orphans_objects_of_its_pods() {
+        let template = PodTemplateSpec {
+            spec: Some(pod_spec("app-settings", "shared", "app-data")),
+            ..PodTemplateSpec::default()
+        };
+        let cfgmap = |name: &str, owner_uid: Option<&str>| ConfigMap {
+            metadata: metadata(name, owner_uid),
+            ..ConfigMap::default()
+        };
+        let resources = NamespaceResources {
+            cfgmaps: vec![
+                cfgmap("app-settings", None),
+                cfgmap("other", None),
+                cfgmap("unused", None),
+                // Deleted by the garbage collector together with the Deployment.
+                cfgmap("app-owned", Some("app-uid")),
+            ],
+            secrets: vec![Secret {
+                metadata: metadata("shared", None),
+                ..Secret::default()
+            }],
+            deployments: vec![Deployment {
+                metadata: metadata("app", None),
+                spec: Some(DeploymentSpec {
+                    template: template.clone(),
+                    ..DeploymentSpec::default()
+                }),
+                ..Deployment::default()
+            }],
+            replicasets: vec![ReplicaSet {
+                metadata: metadata("app-5d4f8", Some("app-uid")),
+                spec: Some(ReplicaSetSpec {
+                    template: Some(template),
+                    ..ReplicaSetSpec::default()
+                }),
+                ..ReplicaSet::default()
+            }],
+            pods: vec![
+                Pod {
+                    metadata: metadata("app-5d4f8-x2x7q", Some("app-5d4f8-uid")),
+                    spec: Some(pod_spec("app-owned", "shared", "app-data")),
+                    ..Pod::default()
+                },
+                Pod {
+                    metadata: metadata("other", None),
+                    spec: Some(pod_spec("other", "shared", "other-data")),
+                    ..Pod::default()
+                },
+            ],
+            ..NamespaceResources::default()
+        };
+        let claims: Vec<PersistentVolumeClaim> = ["app-data", "other-data"]
+            .into_iter()
+            .map(|name| PersistentVolumeClaim {
+                metadata: metadata(name, None),
+                ..PersistentVolumeClaim::default()
+            })
+            .collect();
+
+        let workloads = resources.pod_spec_referrers();
+        let objects = namespaced_metadata(&resources);
+        let targets = vec![WorkloadRef {
+            kind: WorkloadKind::Deployment,
+            name: "app".to_string(),
+        }];
+        let deleted = find_deleted(&workloads, &objects, &targets).unwrap();
+        assert_eq!(
+            deleted,
+            HashSet::from([
+                "app-uid",
+                "app-owned-uid",
+                "app-5d4f8-uid",
+                "app-5d4f8-x2x7q-uid"
+            ])
+        );
+
+        assert_eq!(
+            find_impact("default", &resources, &claims, &deleted, Utc::now()),
+            Impact {
+                configmaps: HashSet::from(["app-settings".to_string()]),
+                secrets: HashMap::new(),
+                persistent_volume_claims: HashSet::from(["app-data".to_string()]),
+            }
+        );
+
+        let missing = vec![WorkloadRef {
+            kind: WorkloadKind::StatefulSet,
+            name: "app".to_string(),
+        }];
+        assert!(find_deleted(&workloads, &objects, &missing).is_err());
+    }
+
+    #[test]
+    fn object_with_surviving_owner_is_kept() {
+        let owned_by = |name: &str, owner_uids: &[&str]| ConfigMap {
+            metadata: ObjectMeta {
+                owner_references: Some(
+                    owner_uids
+                        .iter()
+                        .map(|uid| OwnerReference {
+                            uid: uid.to_string(),
+                            ..OwnerReference::default()
+                        })
+                        .collect(),
+                ),
+                ..metadata(name, None)
+            },
+            ..ConfigMap::default()
+        };
+        let pod = |name: &str| Pod {
+            metadata: metadata(name, None),
+            spec: Some(PodSpec::default()),
+            ..Pod::default()
+        };
+        let resources = NamespaceResources {
+            cfgmaps: vec![
+                owned_by("co-owned", &["app-uid", "other-uid"]),
+                owned_by("app-owned", &["app-uid"]),
+                ConfigMap {
+                    metadata: metadata("unowned", None),
+                    ..ConfigMap::default()
+                },
+            ],
+            pods: vec![pod("app"), pod("other")],
+            ..NamespaceResources::default()
+        };
+
+        let workloads = resources.pod_spec_referrers();
+        let objects = namespaced_metadata(&resources);
+        let targets = vec![WorkloadRef {
+            kind: WorkloadKind::Pod,
+            name: "app".to_string(),
+        }];
+        assert_eq!(
+            find_deleted(&workloads, &objects, &targets).unwrap(),
+            HashSet::from(["app-uid", "app-owned-uid"])
+        );
+    }
+}
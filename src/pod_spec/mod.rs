@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::option::Option;
 
 use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
@@ -5,41 +6,114 @@ use k8s_openapi::api::batch::v1::CronJob;
 use k8s_openapi::api::batch::v1::Job;
 use k8s_openapi::api::core::v1::{Pod, PodSpec, ReplicationController};
 
+use crate::workloads::WorkloadKind;
+
+/// Mode of files projected from volumes when neither an item's `mode` nor `defaultMode` is set.
+pub const DEFAULT_VOLUME_MODE: i32 = 0o644;
+
 pub trait ResourceWithPodSpec {
+    const WORKLOAD_KIND: WorkloadKind;
+
     fn pod_template_spec(&self) -> Option<&PodSpec>;
+
+    /// Labels of the pod template, or of the pod itself.
+    fn pod_template_labels(&self) -> Option<&BTreeMap<String, String>>;
 }
 
 impl ResourceWithPodSpec for Deployment {
+    const WORKLOAD_KIND: WorkloadKind = WorkloadKind::Deployment;
+
     fn pod_template_spec(&self) -> Option<&PodSpec> {
         self.spec.as_ref()?.template.spec.as_ref()
     }
+
+    fn pod_template_labels(&self) -> Option<&BTreeMap<String, String>> {
+        self.spec
+            .as_ref()?
+            .template
+            .metadata
+            .as_ref()?
+            .labels
+            .as_ref()
+    }
 }
 
 impl ResourceWithPodSpec for ReplicaSet {
+    const WORKLOAD_KIND: WorkloadKind = WorkloadKind::ReplicaSet;
+
     fn pod_template_spec(&self) -> Option<&PodSpec> {
         self.spec.as_ref()?.template.as_ref()?.spec.as_ref()
     }
+
+    fn pod_template_labels(&self) -> Option<&BTreeMap<String, String>> {
+        self.spec
+            .as_ref()?
+            .template
+            .as_ref()?
+            .metadata
+            .as_ref()?
+            .labels
+            .as_ref()
+    }
 }
 
 impl ResourceWithPodSpec for StatefulSet {
+    const WORKLOAD_KIND: WorkloadKind = WorkloadKind::StatefulSet;
+
     fn pod_template_spec(&self) -> Option<&PodSpec> {
         self.spec.as_ref()?.template.spec.as_ref()
     }
+
+    fn pod_template_labels(&self) -> Option<&BTreeMap<String, String>> {
+        self.spec
+            .as_ref()?
+            .template
+            .metadata
+            .as_ref()?
+            .labels
+            .as_ref()
+    }
 }
 
 impl ResourceWithPodSpec for DaemonSet {
+    const WORKLOAD_KIND: WorkloadKind = WorkloadKind::DaemonSet;
+
     fn pod_template_spec(&self) -> Option<&PodSpec> {
         self.spec.as_ref()?.template.spec.as_ref()
     }
+
+    fn pod_template_labels(&self) -> Option<&BTreeMap<String, String>> {
+        self.spec
+            .as_ref()?
+            .template
+            .metadata
+            .as_ref()?
+            .labels
+            .as_ref()
+    }
 }
 
 impl ResourceWithPodSpec for Job {
+    const WORKLOAD_KIND: WorkloadKind = WorkloadKind::Job;
+
     fn pod_template_spec(&self) -> Option<&PodSpec> {
         self.spec.as_ref()?.template.spec.as_ref()
     }
+
+    fn pod_template_labels(&self) -> Option<&BTreeMap<String, String>> {
+        self.spec
+            .as_ref()?
+            .template
+            .metadata
+            .as_ref()?
+            .labels
+            .as_ref()
+    }
 }
 
 impl ResourceWithPodSpec for CronJob {
+    const WORKLOAD_KIND: WorkloadKind = WorkloadKind::CronJob;
+
     fn pod_template_spec(&self) -> Option<&PodSpec> {
         self.spec
             .as_ref()?
@@ -50,18 +124,50 @@ impl ResourceWithPodSpec for CronJob {
             .spec
             .as_ref()
     }
+
+    fn pod_template_labels(&self) -> Option<&BTreeMap<String, String>> {
+        self.spec
+            .as_ref()?
+            .job_template
+            .spec
+            .as_ref()?
+            .template
+            .metadata
+            .as_ref()?
+            .labels
+            .as_ref()
+    }
 }
 
 impl ResourceWithPodSpec for ReplicationController {
+    const WORKLOAD_KIND: WorkloadKind = WorkloadKind::ReplicationController;
+
     fn pod_template_spec(&self) -> Option<&PodSpec> {
         self.spec.as_ref()?.template.as_ref()?.spec.as_ref()
     }
+
+    fn pod_template_labels(&self) -> Option<&BTreeMap<String, String>> {
+        self.spec
+            .as_ref()?
+            .template
+            .as_ref()?
+            .metadata
+            .as_ref()?
+            .labels
+            .as_ref()
+    }
 }
 
 impl ResourceWithPodSpec for Pod {
+    const WORKLOAD_KIND: WorkloadKind = WorkloadKind::Pod;
+
     fn pod_template_spec(&self) -> Option<&PodSpec> {
         self.spec.as_ref()
     }
+
+    fn pod_template_labels(&self) -> Option<&BTreeMap<String, String>> {
+        self.metadata.labels.as_ref()
+    }
 }
 
 #[cfg(test)]
@@ -1,11 +1,14 @@
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 use k8s_openapi::serde::__private::fmt::Debug;
 use k8s_openapi::serde::de::DeserializeOwned;
-use kube::api::ListParams;
-use kube::core::NamespaceResourceScope;
+use kube::api::{ApiResource, DynamicObject, ListParams};
+use kube::core::{ClusterResourceScope, NamespaceResourceScope};
 use kube::{Api, Client, Resource};
 
 use crate::Error;
 
+const PAGE_SIZE: u32 = 500;
+
 pub async fn list_resource<T>(client: &Client, namespace: &str) -> Result<Vec<T>, Error>
 where
     T: Clone + Debug + DeserializeOwned + Resource,
@@ -15,3 +18,147 @@ where
     let resource_api = Api::<T>::namespaced(client.clone(), namespace);
     Ok(resource_api.list(&ListParams::default()).await?.items)
 }
+
+pub async fn get_resource<T>(client: &Client, namespace: &str, name: &str) -> Result<T, Error>
+where
+    T: Clone + Debug + DeserializeOwned + Resource,
+    <T as Resource>::DynamicType: Default,
+    T: Resource<Scope = NamespaceResourceScope>,
+{
+    let resource_api = Api::<T>::namespaced(client.clone(), namespace);
+    Ok(resource_api.get(name).await?)
+}
+
+pub async fn list_resource_in_all_namespaces<T>(client: &Client) -> Result<Vec<T>, Error>
+where
+    T: Clone + Debug + DeserializeOwned + Resource,
+    <T as Resource>::DynamicType: Default,
+    T: Resource<Scope = NamespaceResourceScope>,
+{
+    let resource_api = Api::<T>::all(client.clone());
+    Ok(resource_api.list(&ListParams::default()).await?.items)
+}
+
+pub async fn list_cluster_resource<T>(client: &Client) -> Result<Vec<T>, Error>
+where
+    T: Clone + Debug + DeserializeOwned + Resource,
+    <T as Resource>::DynamicType: Default,
+    T: Resource<Scope = ClusterResourceScope>,
+{
+    let resource_api = Api::<T>::all(client.clone());
+    Ok(resource_api.list(&ListParams::default()).await?.items)
+}
+
+/// Lists cluster-scoped resources the user might not be allowed to read, e.g. with namespaced RBAC only.
+/// When access is forbidden or the resource is unknown, `None` is returned.
+pub async fn list_optional_cluster_resource<T>(client: &Client) -> Result<Option<Vec<T>>, Error>
+where
+    T: Clone + Debug + DeserializeOwned + Resource,
+    <T as Resource>::DynamicType: Default,
+    T: Resource<Scope = ClusterResourceScope>,
+{
+    let resource_api = Api::<T>::all(client.clone());
+    match resource_api.list(&ListParams::default()).await {
+        Ok(list) => Ok(Some(list.items)),
+        Err(kube::Error::Api(response)) if response.code == 403 || response.code == 404 => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Lists namespaced resources the user might not be allowed to read, e.g. with restricted RBAC.
+/// When access is forbidden or the resource is unknown, `None` is returned.
+pub async fn list_optional_namespaced_resource<T>(
+    client: &Client,
+    namespace: &str,
+) -> Result<Option<Vec<T>>, Error>
+where
+    T: Clone + Debug + DeserializeOwned + Resource,
+    <T as Resource>::DynamicType: Default,
+    T: Resource<Scope = NamespaceResourceScope>,
+{
+    let resource_api = Api::<T>::namespaced(client.clone(), namespace);
+    match resource_api.list(&ListParams::default()).await {
+        Ok(list) => Ok(Some(list.items)),
+        Err(kube::Error::Api(response)) if response.code == 403 || response.code == 404 => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Lists only the metadata of resources, e.g. to check which names exist without fetching their content.
+pub async fn list_resource_metadata<T>(
+    client: &Client,
+    namespace: &str,
+) -> Result<Vec<ObjectMeta>, Error>
+where
+    T: Clone + Debug + DeserializeOwned + Resource,
+    <T as Resource>::DynamicType: Default,
+    T: Resource<Scope = NamespaceResourceScope>,
+{
+    let resource_api = Api::<T>::namespaced(client.clone(), namespace);
+    let list = resource_api.list_metadata(&ListParams::default()).await?;
+    Ok(list
+        .items
+        .into_iter()
+        .map(|object| object.metadata)
+        .collect())
+}
+
+/// Lists only the metadata of cluster-scoped resources the user might not be allowed to read.
+/// When access is forbidden or the resource is unknown, `None` is returned.
+pub async fn list_optional_cluster_resource_metadata<T>(
+    client: &Client,
+) -> Result<Option<Vec<ObjectMeta>>, Error>
+where
+    T: Clone + Debug + DeserializeOwned + Resource,
+    <T as Resource>::DynamicType: Default,
+    T: Resource<Scope = ClusterResourceScope>,
+{
+    let resource_api = Api::<T>::all(client.clone());
+    match resource_api.list_metadata(&ListParams::default()).await {
+        Ok(list) => Ok(Some(
+            list.items
+                .into_iter()
+                .map(|object| object.metadata)
+                .collect(),
+        )),
+        Err(kube::Error::Api(response)) if response.code == 403 || response.code == 404 => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Lists resources of a type that might not be installed in the cluster, e.g. a custom resource.
+/// When access is forbidden or the API server does not know the resource, `None` is returned.
+pub async fn list_optional_resource(
+    client: &Client,
+    namespace: &str,
+    api_resource: &ApiResource,
+) -> Result<Option<Vec<DynamicObject>>, Error> {
+    let resource_api =
+        Api::<DynamicObject>::namespaced_with(client.clone(), namespace, api_resource);
+    match resource_api.list(&ListParams::default()).await {
+        Ok(list) => Ok(Some(list.items)),
+        Err(kube::Error::Api(response)) if response.code == 403 || response.code == 404 => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Counts all instances of the `api_resource` across namespaces. Only metadata is listed, page by page,
+/// to keep the load on the API server low.
+pub async fn count_resource_instances(
+    client: &Client,
+    api_resource: &ApiResource,
+) -> Result<usize, Error> {
+    let resource_api = Api::<DynamicObject>::all_with(client.clone(), api_resource);
+    let mut list_params = ListParams::default().limit(PAGE_SIZE);
+    let mut count = 0;
+    loop {
+        let page = resource_api.list_metadata(&list_params).await?;
+        count += page.items.len();
+        match page.metadata.continue_ {
+            Some(continue_token) if !continue_token.is_empty() => {
+                list_params = list_params.continue_token(&continue_token);
+            }
+            _ => return Ok(count),
+        }
+    }
+}
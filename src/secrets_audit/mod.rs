@@ -0,0 +1,401 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use anyhow::Result;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::{KeyToPath, Pod, ReplicationController, Secret};
+use kube::Client;
+use serde::Serialize;
+
+use crate::orphans::references::{
+    extend_with_referrers, pod_spec_references, ObjectKind, PodSpecReferrer,
+};
+use crate::orphans::scaling::KNOWN_WORKLOAD_KINDS;
+use crate::pod_spec::DEFAULT_VOLUME_MODE;
+use crate::resources::list_resource;
+
+/// Permissions beyond the default `0644`, e.g. write access for the group or others, or execution.
+const BEYOND_DEFAULT_MODE_BITS: i32 = 0o777 & !DEFAULT_VOLUME_MODE;
+
+pub async fn audit_secrets(client: &Client, namespace: &str) -> Result<SecretsAudit> {
+    let (
+        secrets,
+        deployments,
+        replicasets,
+        statefulsets,
+        daemonsets,
+        jobs,
+        cronjobs,
+        replication_controllers,
+        pods,
+    ) = tokio::try_join!(
+        list_resource::<Secret>(client, namespace),
+        list_resource::<Deployment>(client, namespace),
+        list_resource::<ReplicaSet>(client, namespace),
+        list_resource::<StatefulSet>(client, namespace),
+        list_resource::<DaemonSet>(client, namespace),
+        list_resource::<Job>(client, namespace),
+        list_resource::<CronJob>(client, namespace),
+        list_resource::<ReplicationController>(client, namespace),
+        list_resource::<Pod>(client, namespace)
+    )?;
+
+    let mut workloads = Vec::new();
+    extend_with_referrers(&mut workloads, &deployments);
+    extend_with_referrers(&mut workloads, &replicasets);
+    extend_with_referrers(&mut workloads, &statefulsets);
+    extend_with_referrers(&mut workloads, &daemonsets);
+    extend_with_referrers(&mut workloads, &jobs);
+    extend_with_referrers(&mut workloads, &cronjobs);
+    extend_with_referrers(&mut workloads, &replication_controllers);
+    extend_with_referrers(&mut workloads, &pods);
+
+    Ok(audit_workloads(&workloads, &secrets))
+}
+
+/// Whether the workload is controlled by another workload, so it is inspected through its
+/// controller's pod template only.
+fn is_controlled(workload: &PodSpecReferrer) -> bool {
+    workload
+        .metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .any(|owner| {
+            owner.controller == Some(true) && KNOWN_WORKLOAD_KINDS.contains(&owner.kind.as_str())
+        })
+}
+
+/// Finds containers injecting `Secret`s as environment variables and `Secret` volumes with files
+/// accessible beyond the default `0644` mode. Workloads with a controller are skipped.
+pub fn audit_workloads(workloads: &[PodSpecReferrer], secrets: &[Secret]) -> SecretsAudit {
+    let secret_keys: HashMap<&str, usize> = secrets
+        .iter()
+        .filter_map(|secret| {
+            let keys = secret.data.as_ref().map_or(0, BTreeMap::len);
+            Some((secret.metadata.name.as_deref()?, keys))
+        })
+        .collect();
+
+    let mut env_vars = Vec::new();
+    let mut broad_mounts = Vec::new();
+    for workload in workloads.iter().filter(|workload| !is_controlled(workload)) {
+        let Some(name) = workload.metadata.name.as_deref() else {
+            continue;
+        };
+        let workload_name = format!("{}/{name}", workload.kind);
+        let pod_spec = workload.pod_spec;
+        for (container, secrets) in exposed_secrets(workload, &secret_keys) {
+            env_vars.push(SecretEnvVars {
+                workload: workload_name.clone(),
+                container,
+                keys_exposed: secrets.iter().map(|secret| secret.keys).sum(),
+                secrets,
+            });
+        }
+
+        for volume in pod_spec.volumes.iter().flatten() {
+            // Secrets with the modes of their files, following `items` and `defaultMode`.
+            let sources: Vec<(&String, Vec<i32>)> = match (&volume.secret, &volume.projected) {
+                (Some(secret), _) => {
+                    let default_mode = secret.default_mode.unwrap_or(DEFAULT_VOLUME_MODE);
+                    secret
+                        .secret_name
+                        .iter()
+                        .map(|name| (name, file_modes(secret.items.as_deref(), default_mode)))
+                        .collect()
+                }
+                (None, Some(projected)) => {
+                    let default_mode = projected.default_mode.unwrap_or(DEFAULT_VOLUME_MODE);
+                    projected
+                        .sources
+                        .iter()
+                        .flatten()
+                        .filter_map(|source| source.secret.as_ref())
+                        .map(|secret| {
+                            let modes = file_modes(secret.items.as_deref(), default_mode);
+                            (&secret.name, modes)
+                        })
+                        .collect()
+                }
+                (None, None) => continue,
+            };
+
+            let mut secrets = Vec::new();
+            let mut modes = BTreeSet::new();
+            for (secret, file_modes) in sources {
+                let broad_modes: Vec<i32> = file_modes
+                    .into_iter()
+                    .filter(|mode| mode & BEYOND_DEFAULT_MODE_BITS != 0)
+                    .collect();
+                if !broad_modes.is_empty() {
+                    secrets.push(secret.clone());
+                    modes.extend(broad_modes);
+                }
+            }
+            if secrets.is_empty() {
+                continue;
+            }
+            broad_mounts.push(BroadMount {
+                workload: workload_name.clone(),
+                volume: volume.name.clone(),
+                secrets,
+                modes: modes.iter().map(|mode| format!("{mode:04o}")).collect(),
+            });
+        }
+    }
+
+    SecretsAudit {
+        env_vars,
+        broad_mounts,
+    }
+}
+
+/// Modes of files projected from a `Secret`: the `items`' own modes, falling back to the `default_mode`.
+fn file_modes(items: Option<&[KeyToPath]>, default_mode: i32) -> Vec<i32> {
+    match items {
+        Some(items) => items
+            .iter()
+            .map(|item| item.mode.unwrap_or(default_mode))
+            .collect(),
+        None => vec![default_mode],
+    }
+}
+
+/// `Secret`s injected into environment variables, by container. `Secret`s referenced by `envFrom`
+/// expose all of their keys, unless the `Secret` is missing.
+fn exposed_secrets(
+    workload: &PodSpecReferrer,
+    secret_keys: &HashMap<&str, usize>,
+) -> Vec<(String, Vec<ExposedSecret>)> {
+    let mut containers: Vec<(String, BTreeMap<String, ExposedSecret>)> = Vec::new();
+    for reference in pod_spec_references(workload.pod_spec, workload.kind.pod_spec_path()) {
+        let (Some(container), ObjectKind::Secret) = (reference.container, reference.object.kind)
+        else {
+            continue;
+        };
+        let idx = match containers.iter().position(|(name, _)| *name == container) {
+            Some(idx) => idx,
+            None => {
+                containers.push((container, BTreeMap::new()));
+                containers.len() - 1
+            }
+        };
+        let secret = reference.object.name;
+        let exposed_secret = containers[idx]
+            .1
+            .entry(secret.clone())
+            .or_insert_with(|| ExposedSecret::new(&secret));
+        if reference.path.ends_with(".secretRef") {
+            exposed_secret.env_from = true;
+            exposed_secret.keys = secret_keys
+                .get(secret.as_str())
+                .copied()
+                .unwrap_or_default();
+        } else if !exposed_secret.env_from {
+            exposed_secret.keys += 1;
+        }
+        exposed_secret.paths.push(reference.path);
+    }
+    containers
+        .into_iter()
+        .map(|(container, secrets)| (container, secrets.into_values().collect()))
+        .collect()
+}
+
+/// A container with environment variables taken from `Secret`s.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SecretEnvVars {
+    pub workload: String,
+    pub container: String,
+    pub keys_exposed: usize,
+    pub secrets: Vec<ExposedSecret>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ExposedSecret {
+    pub secret: String,
+    pub env_from: bool,
+    pub keys: usize,
+    /// JSON paths of the references in the workload.
+    pub paths: Vec<String>,
+}
+
+impl ExposedSecret {
+    fn new(secret: &str) -> Self {
+        ExposedSecret {
+            secret: secret.to_string(),
+            env_from: false,
+            keys: 0,
+            paths: Vec::new(),
+        }
+    }
+}
+
+/// A `Secret` or projected volume with files accessible beyond the default mode, with their modes in octal.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct BroadMount {
+    pub workload: String,
+    pub volume: String,
+    pub secrets: Vec<String>,
+    pub modes: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct SecretsAudit {
+    pub env_vars: Vec<SecretEnvVars>,
+    pub broad_mounts: Vec<BroadMount>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::api::apps::v1::{Deployment, DeploymentSpec};
+    use k8s_openapi::api::core::v1::{
+        Container, EnvFromSource, EnvVar, EnvVarSource, KeyToPath, PodSpec, PodTemplateSpec,
+        Secret, SecretEnvSource, SecretKeySelector, SecretVolumeSource, Volume,
+    };
+    use k8s_openapi::ByteString;
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::references::extend_with_referrers;
+    use crate::secrets_audit::{audit_workloads, BroadMount, ExposedSecret, SecretEnvVars};
+
+    #[test]
+    fn secrets_in_env_vars_and_broad_mounts() {
+        let secrets = vec![Secret {
+            metadata: ObjectMeta {
+                name: Some("credentials".to_string()),
+                ..ObjectMeta::default()
+            },
+            data: Some(BTreeMap::from([
+                ("username".to_string(), ByteString(b"admin".to_vec())),
+                ("password".to_string(), ByteString(b"secret".to_vec())),
+            ])),
+            ..Secret::default()
+        }];
+        let pod_spec = PodSpec {
+            containers: vec![Container {
+                name: "app".to_string(),
+                env_from: Some(vec![EnvFromSource {
+                    secret_ref: Some(SecretEnvSource {
+                        name: "credentials".to_string(),
+                        ..SecretEnvSource::default()
+                    }),
+                    ..EnvFromSource::default()
+                }]),
+                env: Some(vec![EnvVar {
+                    name: "TOKEN".to_string(),
+                    value_from: Some(EnvVarSource {
+                        secret_key_ref: Some(SecretKeySelector {
+                            name: "api-token".to_string(),
+                            key: "token".to_string(),
+                            ..SecretKeySelector::default()
+                        }),
+                        ..EnvVarSource::default()
+                    }),
+                    ..EnvVar::default()
+                }]),
+                ..Container::default()
+            }],
+            volumes: Some(vec![
+                Volume {
+                    name: "certs".to_string(),
+                    secret: Some(SecretVolumeSource {
+                        secret_name: Some("certs".to_string()),
+                        default_mode: Some(0o400),
+                        items: Some(vec![
+                            KeyToPath {
+                                key: "tls.key".to_string(),
+                                path: "tls.key".to_string(),
+                                ..KeyToPath::default()
+                            },
+                            KeyToPath {
+                                key: "tls.crt".to_string(),
+                                path: "tls.crt".to_string(),
+                                mode: Some(0o666),
+                            },
+                        ]),
+                        ..SecretVolumeSource::default()
+                    }),
+                    ..Volume::default()
+                },
+                Volume {
+                    name: "private".to_string(),
+                    secret: Some(SecretVolumeSource {
+                        secret_name: Some("private".to_string()),
+                        default_mode: Some(0o400),
+                        ..SecretVolumeSource::default()
+                    }),
+                    ..Volume::default()
+                },
+                Volume {
+                    name: "defaults".to_string(),
+                    secret: Some(SecretVolumeSource {
+                        secret_name: Some("defaults".to_string()),
+                        ..SecretVolumeSource::default()
+                    }),
+                    ..Volume::default()
+                },
+            ]),
+            ..PodSpec::default()
+        };
+
+        let deployments = vec![Deployment {
+            metadata: ObjectMeta {
+                name: Some("app".to_string()),
+                ..ObjectMeta::default()
+            },
+            spec: Some(DeploymentSpec {
+                template: PodTemplateSpec {
+                    spec: Some(pod_spec),
+                    ..PodTemplateSpec::default()
+                },
+                ..DeploymentSpec::default()
+            }),
+            ..Deployment::default()
+        }];
+        let mut workloads = Vec::new();
+        extend_with_referrers(&mut workloads, &deployments);
+
+        let audit = audit_workloads(&workloads, &secrets);
+
+        assert_eq!(
+            audit.env_vars,
+            vec![SecretEnvVars {
+                workload: "Deployment/app".to_string(),
+                container: "app".to_string(),
+                keys_exposed: 3,
+                secrets: vec![
+                    ExposedSecret {
+                        secret: "api-token".to_string(),
+                        env_from: false,
+                        keys: 1,
+                        paths: vec![
+                            "spec.template.spec.containers[0].env[0].valueFrom.secretKeyRef"
+                                .to_string()
+                        ],
+                    },
+                    ExposedSecret {
+                        secret: "credentials".to_string(),
+                        env_from: true,
+                        keys: 2,
+                        paths: vec![
+                            "spec.template.spec.containers[0].envFrom[0].secretRef".to_string()
+                        ],
+                    },
+                ],
+            }]
+        );
+        assert_eq!(
+            audit.broad_mounts,
+            vec![BroadMount {
+                workload: "Deployment/app".to_string(),
+                volume: "certs".to_string(),
+                secrets: vec!["certs".to_string()],
+                modes: vec!["0666".to_string()],
+            }]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, LabelSelectorRequirement};
+
+/// Evaluates a `LabelSelector` against given `labels`, following Kubernetes semantics.
+/// An empty selector matches everything.
+pub fn selector_matches(
+    selector: &LabelSelector,
+    labels: Option<&BTreeMap<String, String>>,
+) -> bool {
+    let empty = BTreeMap::new();
+    let labels = labels.unwrap_or(&empty);
+
+    let labels_match = selector.match_labels.iter().flatten().all(|(key, value)| {
+        labels
+            .get(key)
+            .is_some_and(|label_value| label_value == value)
+    });
+
+    labels_match
+        && selector
+            .match_expressions
+            .iter()
+            .flatten()
+            .all(|requirement| requirement_matches(requirement, labels))
+}
+
+fn requirement_matches(
+    requirement: &LabelSelectorRequirement,
+    labels: &BTreeMap<String, String>,
+) -> bool {
+    let label_value = labels.get(&requirement.key);
+    let values_contain = |value: &String| {
+        requirement
+            .values
+            .iter()
+            .flatten()
+            .any(|requirement_value| requirement_value == value)
+    };
+
+    match requirement.operator.as_str() {
+        "In" => label_value.is_some_and(values_contain),
+        "NotIn" => !label_value.is_some_and(values_contain),
+        "Exists" => label_value.is_some(),
+        "DoesNotExist" => label_value.is_none(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, LabelSelectorRequirement};
+
+    use crate::selector::selector_matches;
+
+    #[test]
+    fn empty_selector_matches_everything() {
+        assert!(selector_matches(&LabelSelector::default(), None));
+    }
+
+    #[test]
+    fn match_labels_and_expressions() {
+        let labels = BTreeMap::from([
+            ("app".to_string(), "nginx".to_string()),
+            ("tier".to_string(), "frontend".to_string()),
+        ]);
+        let selector = LabelSelector {
+            match_labels: Some(BTreeMap::from([("app".to_string(), "nginx".to_string())])),
+            match_expressions: Some(vec![
+                LabelSelectorRequirement {
+                    key: "tier".to_string(),
+                    operator: "In".to_string(),
+                    values: Some(vec!["frontend".to_string(), "backend".to_string()]),
+                },
+                LabelSelectorRequirement {
+                    key: "canary".to_string(),
+                    operator: "DoesNotExist".to_string(),
+                    values: None,
+                },
+            ]),
+        };
+        assert!(selector_matches(&selector, Some(&labels)));
+
+        let other_labels = BTreeMap::from([("app".to_string(), "redis".to_string())]);
+        assert!(!selector_matches(&selector, Some(&other_labels)));
+        assert!(!selector_matches(&selector, None));
+    }
+}
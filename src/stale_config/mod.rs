@@ -0,0 +1,280 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Result;
+use k8s_openapi::api::core::v1::{ConfigMap, Container, Pod, PodSpec, Secret};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+use kube::Client;
+use serde::Serialize;
+
+use crate::age::last_modified;
+use crate::orphans::references::{pod_spec_references, ObjectKind, ObjectRef};
+use crate::resources::list_resource;
+
+pub async fn find_stale_config(client: &Client, namespace: &str) -> Result<StaleConfig> {
+    let (pods, cfgmaps, secrets) = tokio::try_join!(
+        list_resource::<Pod>(client, namespace),
+        list_resource::<ConfigMap>(client, namespace),
+        list_resource::<Secret>(client, namespace)
+    )?;
+    Ok(StaleConfig {
+        containers: find_stale_containers(&pods, &cfgmaps, &secrets),
+    })
+}
+
+/// Finds running containers, including sidecar init containers, started before the last modification
+/// of a `ConfigMap` or `Secret` they consume through environment variables or `subPath` mounts.
+/// Neither is updated without a restart, unlike regular volume mounts.
+pub fn find_stale_containers(
+    pods: &[Pod],
+    cfgmaps: &[ConfigMap],
+    secrets: &[Secret],
+) -> Vec<StaleContainer> {
+    let mut modified: HashMap<(ObjectKind, &str), &Time> = HashMap::new();
+    extend_with_modified(
+        &mut modified,
+        ObjectKind::ConfigMap,
+        cfgmaps.iter().map(|c| &c.metadata),
+    );
+    extend_with_modified(
+        &mut modified,
+        ObjectKind::Secret,
+        secrets.iter().map(|s| &s.metadata),
+    );
+
+    let mut stale_containers = Vec::new();
+    for pod in pods {
+        let (Some(name), Some(spec), Some(status)) = (&pod.metadata.name, &pod.spec, &pod.status)
+        else {
+            continue;
+        };
+        if status.phase.as_deref() != Some("Running") {
+            continue;
+        }
+
+        let references = pod_spec_references(spec, "spec");
+        // Init containers only run alongside the app containers as sidecars, which report a running state.
+        let containers = [
+            (
+                "initContainers",
+                spec.init_containers.as_deref().unwrap_or_default(),
+                status
+                    .init_container_statuses
+                    .as_deref()
+                    .unwrap_or_default(),
+                None,
+            ),
+            (
+                "containers",
+                spec.containers.as_slice(),
+                status.container_statuses.as_deref().unwrap_or_default(),
+                status.start_time.as_ref(),
+            ),
+        ];
+        for (field, containers, container_statuses, pod_started_at) in containers {
+            for (idx, container) in containers.iter().enumerate() {
+                let started_at = container_statuses
+                    .iter()
+                    .find(|container_status| container_status.name == container.name)
+                    .and_then(|container_status| container_status.state.as_ref()?.running.as_ref())
+                    .and_then(|running| running.started_at.as_ref())
+                    .or(pod_started_at);
+                let Some(started_at) = started_at else {
+                    continue;
+                };
+
+                let read_paths = read_paths(&format!("spec.{field}[{idx}]"), container, spec);
+                let read: BTreeSet<&ObjectRef> = references
+                    .iter()
+                    .filter(|reference| {
+                        read_paths
+                            .iter()
+                            .any(|prefix| reference.path.starts_with(prefix))
+                    })
+                    .map(|reference| &reference.object)
+                    .collect();
+                let changed: Vec<ChangedObject> = read
+                    .into_iter()
+                    .filter_map(|object| {
+                        let modified_at = modified.get(&(object.kind, object.name.as_str()))?;
+                        (modified_at.0 > started_at.0).then(|| ChangedObject {
+                            object: object.to_string(),
+                            modified_at: (*modified_at).clone(),
+                        })
+                    })
+                    .collect();
+                if !changed.is_empty() {
+                    stale_containers.push(StaleContainer {
+                        pod: name.clone(),
+                        container: container.name.clone(),
+                        started_at: started_at.clone(),
+                        changed,
+                    });
+                }
+            }
+        }
+    }
+    stale_containers
+}
+
+fn extend_with_modified<'a>(
+    modified: &mut HashMap<(ObjectKind, &'a str), &'a Time>,
+    kind: ObjectKind,
+    objects: impl Iterator<Item = &'a ObjectMeta>,
+) {
+    let ext_modified = objects
+        .filter_map(|metadata| Some(((kind, metadata.name.as_deref()?), last_modified(metadata)?)));
+    modified.extend(ext_modified);
+}
+
+/// Prefixes of pod spec reference paths the `container` at `container_path` reads without being updated:
+/// its own environment variables and volumes it mounts with a `subPath`.
+fn read_paths(container_path: &str, container: &Container, spec: &PodSpec) -> Vec<String> {
+    let sub_path_volumes = container
+        .volume_mounts
+        .iter()
+        .flatten()
+        .filter(|mount| mount.sub_path.is_some() || mount.sub_path_expr.is_some())
+        .filter_map(|mount| {
+            spec.volumes
+                .iter()
+                .flatten()
+                .position(|volume| volume.name == mount.name)
+        })
+        .map(|volume_idx| format!("spec.volumes[{volume_idx}]."));
+    std::iter::once(format!("{container_path}."))
+        .chain(sub_path_volumes)
+        .collect()
+}
+
+/// A running container started before a `ConfigMap` or `Secret` it reads was modified.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct StaleContainer {
+    pub pod: String,
+    pub container: String,
+    pub started_at: Time,
+    pub changed: Vec<ChangedObject>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ChangedObject {
+    pub object: String,
+    pub modified_at: Time,
+}
+
+#[derive(Serialize)]
+pub struct StaleConfig {
+    pub containers: Vec<StaleContainer>,
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{
+        ConfigMap, ConfigMapVolumeSource, Container, Pod, PodSpec, PodStatus,
+        ProjectedVolumeSource, Secret, SecretProjection, Volume, VolumeMount, VolumeProjection,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+    use k8s_openapi::chrono::{Duration, Utc};
+    use kube::api::ObjectMeta;
+
+    use crate::stale_config::{find_stale_containers, ChangedObject, StaleContainer};
+
+    fn metadata(name: &str, created: Time) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            creation_timestamp: Some(created),
+            ..ObjectMeta::default()
+        }
+    }
+
+    #[test]
+    fn sub_path_mount_modified_after_start() {
+        let now = Utc::now();
+        let started_at = Time(now - Duration::hours(2));
+        let modified_at = Time(now - Duration::hours(1));
+        let mount = |name: &str, sub_path: Option<&str>| VolumeMount {
+            name: name.to_string(),
+            mount_path: format!("/etc/{name}"),
+            sub_path: sub_path.map(str::to_string),
+            ..VolumeMount::default()
+        };
+        let volume = |name: &str| Volume {
+            name: name.to_string(),
+            config_map: Some(ConfigMapVolumeSource {
+                name: name.to_string(),
+                ..ConfigMapVolumeSource::default()
+            }),
+            ..Volume::default()
+        };
+        let pods = vec![Pod {
+            metadata: metadata("web", started_at.clone()),
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "app".to_string(),
+                    volume_mounts: Some(vec![
+                        mount("settings", Some("app.properties")),
+                        mount("dynamic", None),
+                        mount("bundle", Some("token")),
+                    ]),
+                    ..Container::default()
+                }],
+                volumes: Some(vec![
+                    volume("settings"),
+                    volume("dynamic"),
+                    Volume {
+                        name: "bundle".to_string(),
+                        projected: Some(ProjectedVolumeSource {
+                            sources: Some(vec![VolumeProjection {
+                                secret: Some(SecretProjection {
+                                    name: "token".to_string(),
+                                    ..SecretProjection::default()
+                                }),
+                                ..VolumeProjection::default()
+                            }]),
+                            ..ProjectedVolumeSource::default()
+                        }),
+                        ..Volume::default()
+                    },
+                ]),
+                ..PodSpec::default()
+            }),
+            status: Some(PodStatus {
+                phase: Some("Running".to_string()),
+                start_time: Some(started_at.clone()),
+                ..PodStatus::default()
+            }),
+        }];
+        let cfgmaps = vec![
+            ConfigMap {
+                metadata: metadata("settings", modified_at.clone()),
+                ..ConfigMap::default()
+            },
+            ConfigMap {
+                metadata: metadata("dynamic", modified_at.clone()),
+                ..ConfigMap::default()
+            },
+        ];
+        let secrets = vec![Secret {
+            metadata: metadata("token", modified_at.clone()),
+            ..Secret::default()
+        }];
+
+        assert_eq!(
+            find_stale_containers(&pods, &cfgmaps, &secrets),
+            vec![StaleContainer {
+                pod: "web".to_string(),
+                container: "app".to_string(),
+                started_at,
+                changed: vec![
+                    ChangedObject {
+                        object: "ConfigMap/settings".to_string(),
+                        modified_at: modified_at.clone(),
+                    },
+                    ChangedObject {
+                        object: "Secret/token".to_string(),
+                        modified_at,
+                    },
+                ],
+            }]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::{Pod, ReplicationController};
+use kube::Client;
+use serde::Serialize;
+
+use crate::orphans::references::{
+    extend_with_referrers, owner_uids, pod_spec_references, ObjectKind, ObjectRef, PodSpecReferrer,
+    MAX_OWNER_DEPTH,
+};
+use crate::resources::list_resource;
+use crate::workloads::WorkloadRef;
+
+pub async fn find_tree(
+    client: &Client,
+    namespace: &str,
+    workload: &WorkloadRef,
+) -> Result<TreeNode> {
+    let (
+        deployments,
+        replicasets,
+        statefulsets,
+        daemonsets,
+        jobs,
+        cronjobs,
+        replication_controllers,
+        pods,
+    ) = tokio::try_join!(
+        list_resource::<Deployment>(client, namespace),
+        list_resource::<ReplicaSet>(client, namespace),
+        list_resource::<StatefulSet>(client, namespace),
+        list_resource::<DaemonSet>(client, namespace),
+        list_resource::<Job>(client, namespace),
+        list_resource::<CronJob>(client, namespace),
+        list_resource::<ReplicationController>(client, namespace),
+        list_resource::<Pod>(client, namespace)
+    )?;
+
+    let mut objects = Vec::new();
+    extend_with_referrers(&mut objects, &deployments);
+    extend_with_referrers(&mut objects, &replicasets);
+    extend_with_referrers(&mut objects, &statefulsets);
+    extend_with_referrers(&mut objects, &daemonsets);
+    extend_with_referrers(&mut objects, &jobs);
+    extend_with_referrers(&mut objects, &cronjobs);
+    extend_with_referrers(&mut objects, &replication_controllers);
+    extend_with_referrers(&mut objects, &pods);
+
+    build_tree(workload, &objects).ok_or_else(|| anyhow!("{workload} not found"))
+}
+
+/// Builds the ownership tree down from the `workload`, following `ownerReferences` of `objects`.
+/// Pod specs of owned objects are mostly copies of their owner's template, so each level only lists
+/// `ConfigMap`s and `Secret`s not already referenced by its owner.
+pub fn build_tree(workload: &WorkloadRef, objects: &[PodSpecReferrer]) -> Option<TreeNode> {
+    let root = objects.iter().find(|object| {
+        object.kind == workload.kind && object.metadata.name.as_deref() == Some(&workload.name)
+    })?;
+    Some(build_node(root, objects, &BTreeSet::new(), 0))
+}
+
+fn build_node(
+    object: &PodSpecReferrer,
+    objects: &[PodSpecReferrer],
+    owner_references: &BTreeSet<ObjectRef>,
+    depth: usize,
+) -> TreeNode {
+    let references: BTreeSet<ObjectRef> = pod_spec_references(object.pod_spec, "")
+        .into_iter()
+        .map(|reference| reference.object)
+        .collect();
+    let own_references = |kind: ObjectKind| -> Vec<String> {
+        references
+            .difference(owner_references)
+            .filter(|reference| reference.kind == kind)
+            .map(|reference| reference.name.clone())
+            .collect()
+    };
+
+    let mut children: Vec<TreeNode> = match (&object.metadata.uid, depth < MAX_OWNER_DEPTH) {
+        (Some(uid), true) => objects
+            .iter()
+            .filter(|child| owner_uids(child.metadata).any(|owner| owner == uid))
+            .map(|child| build_node(child, objects, &references, depth + 1))
+            .collect(),
+        _ => Vec::new(),
+    };
+    children.sort_by(|a, b| a.object.cmp(&b.object));
+
+    TreeNode {
+        object: format!(
+            "{}/{}",
+            object.kind,
+            object.metadata.name.as_deref().unwrap_or_default()
+        ),
+        configmaps: own_references(ObjectKind::ConfigMap),
+        secrets: own_references(ObjectKind::Secret),
+        children,
+    }
+}
+
+/// A resource with the `ConfigMap`s and `Secret`s it references and the resources it owns.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TreeNode {
+    pub object: String,
+    pub configmaps: Vec<String>,
+    pub secrets: Vec<String>,
+    pub children: Vec<TreeNode>,
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::apps::v1::{Deployment, DeploymentSpec, ReplicaSet, ReplicaSetSpec};
+    use k8s_openapi::api::core::v1::{
+        ConfigMapVolumeSource, Pod, PodSpec, PodTemplateSpec, SecretVolumeSource, Volume,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+    use kube::api::ObjectMeta;
+
+    use crate::orphans::references::extend_with_referrers;
+    use crate::tree::{build_tree, TreeNode};
+    use crate::workloads::{WorkloadKind, WorkloadRef};
+
+    fn metadata(name: &str, owner_uid: Option<&str>) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.to_string()),
+            uid: Some(format!("{name}-uid")),
+            owner_references: owner_uid.map(|uid| {
+                vec![OwnerReference {
+                    uid: uid.to_string(),
+                    ..OwnerReference::default()
+                }]
+            }),
+            ..ObjectMeta::default()
+        }
+    }
+
+    fn cfgmap_volume() -> Volume {
+        Volume {
+            name: "settings".to_string(),
+            config_map: Some(ConfigMapVolumeSource {
+                name: "settings".to_string(),
+                ..ConfigMapVolumeSource::default()
+            }),
+            ..Volume::default()
+        }
+    }
+
+    fn template() -> PodTemplateSpec {
+        PodTemplateSpec {
+            spec: Some(PodSpec {
+                volumes: Some(vec![cfgmap_volume()]),
+                ..PodSpec::default()
+            }),
+            ..PodTemplateSpec::default()
+        }
+    }
+
+    #[test]
+    fn deployment_tree_collapses_inherited_references() {
+        let deployments = vec![Deployment {
+            metadata: metadata("app", None),
+            spec: Some(DeploymentSpec {
+                template: template(),
+                ..DeploymentSpec::default()
+            }),
+            ..Deployment::default()
+        }];
+        let replicasets = vec![ReplicaSet {
+            metadata: metadata("app-5d4f8", Some("app-uid")),
+            spec: Some(ReplicaSetSpec {
+                template: Some(template()),
+                ..ReplicaSetSpec::default()
+            }),
+            ..ReplicaSet::default()
+        }];
+        let injected_secret = Volume {
+            name: "token".to_string(),
+            secret: Some(SecretVolumeSource {
+                secret_name: Some("injected".to_string()),
+                ..SecretVolumeSource::default()
+            }),
+            ..Volume::default()
+        };
+        let pods = vec![Pod {
+            metadata: metadata("app-5d4f8-x2x7q", Some("app-5d4f8-uid")),
+            spec: Some(PodSpec {
+                volumes: Some(vec![cfgmap_volume(), injected_secret]),
+                ..PodSpec::default()
+            }),
+            ..Pod::default()
+        }];
+
+        let mut objects = Vec::new();
+        extend_with_referrers(&mut objects, &deployments);
+        extend_with_referrers(&mut objects, &replicasets);
+        extend_with_referrers(&mut objects, &pods);
+        let workload = WorkloadRef {
+            kind: WorkloadKind::Deployment,
+            name: "app".to_string(),
+        };
+
+        assert_eq!(
+            build_tree(&workload, &objects),
+            Some(TreeNode {
+                object: "Deployment/app".to_string(),
+                configmaps: vec!["settings".to_string()],
+                secrets: Vec::new(),
+                children: vec![TreeNode {
+                    object: "ReplicaSet/app-5d4f8".to_string(),
+                    configmaps: Vec::new(),
+                    secrets: Vec::new(),
+                    children: vec![TreeNode {
+                        object: "Pod/app-5d4f8-x2x7q".to_string(),
+                        configmaps: Vec::new(),
+                        secrets: vec!["injected".to_string()],
+                        children: Vec::new(),
+                    }],
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::{Pod, PodSpec, ReplicationController};
+use kube::Client;
+
+use crate::pod_spec::ResourceWithPodSpec;
+use crate::resources::get_resource;
+
+/// Kinds of resources with a pod spec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WorkloadKind {
+    Pod,
+    Deployment,
+    ReplicaSet,
+    StatefulSet,
+    DaemonSet,
+    Job,
+    CronJob,
+    ReplicationController,
+}
+
+impl WorkloadKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WorkloadKind::Pod => "Pod",
+            WorkloadKind::Deployment => "Deployment",
+            WorkloadKind::ReplicaSet => "ReplicaSet",
+            WorkloadKind::StatefulSet => "StatefulSet",
+            WorkloadKind::DaemonSet => "DaemonSet",
+            WorkloadKind::Job => "Job",
+            WorkloadKind::CronJob => "CronJob",
+            WorkloadKind::ReplicationController => "ReplicationController",
+        }
+    }
+
+    /// JSON path of the pod spec in resources of this kind.
+    pub fn pod_spec_path(self) -> &'static str {
+        match self {
+            WorkloadKind::Pod => "spec",
+            WorkloadKind::CronJob => "spec.jobTemplate.spec.template.spec",
+            WorkloadKind::Deployment
+            | WorkloadKind::ReplicaSet
+            | WorkloadKind::StatefulSet
+            | WorkloadKind::DaemonSet
+            | WorkloadKind::Job
+            | WorkloadKind::ReplicationController => "spec.template.spec",
+        }
+    }
+}
+
+impl FromStr for WorkloadKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pod" | "pods" | "po" => Ok(WorkloadKind::Pod),
+            "deployment" | "deployments" | "deploy" => Ok(WorkloadKind::Deployment),
+            "replicaset" | "replicasets" | "rs" => Ok(WorkloadKind::ReplicaSet),
+            "statefulset" | "statefulsets" | "sts" => Ok(WorkloadKind::StatefulSet),
+            "daemonset" | "daemonsets" | "ds" => Ok(WorkloadKind::DaemonSet),
+            "job" | "jobs" => Ok(WorkloadKind::Job),
+            "cronjob" | "cronjobs" | "cj" => Ok(WorkloadKind::CronJob),
+            "replicationcontroller" | "replicationcontrollers" | "rc" => {
+                Ok(WorkloadKind::ReplicationController)
+            }
+            _ => Err(format!("Unsupported kind '{s}'")),
+        }
+    }
+}
+
+impl Display for WorkloadKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A Pod or workload given on the command line as `kind/name`, or just `name` for a Pod.
+/// Kinds are accepted in singular, plural and short forms, e.g. `deployment`, `deployments` or `deploy`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkloadRef {
+    pub kind: WorkloadKind,
+    pub name: String,
+}
+
+impl FromStr for WorkloadRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, name) = s.split_once('/').unwrap_or(("pod", s));
+        let kind = WorkloadKind::from_str(kind)?;
+        if name.is_empty() {
+            return Err("Missing name".to_string());
+        }
+        Ok(WorkloadRef {
+            kind,
+            name: name.to_string(),
+        })
+    }
+}
+
+impl Display for WorkloadRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.kind, self.name)
+    }
+}
+
+/// Fetches the pod spec of the `workload`, i.e. the Pod's own spec or the workload's pod template.
+pub async fn get_pod_spec(
+    client: &Client,
+    namespace: &str,
+    workload: &WorkloadRef,
+) -> Result<PodSpec> {
+    let name = workload.name.as_str();
+    let pod_spec = match workload.kind {
+        WorkloadKind::Pod => pod_spec_of(get_resource::<Pod>(client, namespace, name).await?),
+        WorkloadKind::Deployment => {
+            pod_spec_of(get_resource::<Deployment>(client, namespace, name).await?)
+        }
+        WorkloadKind::ReplicaSet => {
+            pod_spec_of(get_resource::<ReplicaSet>(client, namespace, name).await?)
+        }
+        WorkloadKind::StatefulSet => {
+            pod_spec_of(get_resource::<StatefulSet>(client, namespace, name).await?)
+        }
+        WorkloadKind::DaemonSet => {
+            pod_spec_of(get_resource::<DaemonSet>(client, namespace, name).await?)
+        }
+        WorkloadKind::Job => pod_spec_of(get_resource::<Job>(client, namespace, name).await?),
+        WorkloadKind::CronJob => {
+            pod_spec_of(get_resource::<CronJob>(client, namespace, name).await?)
+        }
+        WorkloadKind::ReplicationController => {
+            pod_spec_of(get_resource::<ReplicationController>(client, namespace, name).await?)
+        }
+    };
+    pod_spec.ok_or_else(|| anyhow!("{workload} has no pod spec"))
+}
+
+fn pod_spec_of<T: ResourceWithPodSpec>(resource: T) -> Option<PodSpec> {
+    resource.pod_template_spec().cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::workloads::{WorkloadKind, WorkloadRef};
+
+    #[test]
+    fn workload_ref_kinds() {
+        assert_eq!(
+            WorkloadRef::from_str("deploy/app"),
+            Ok(WorkloadRef {
+                kind: WorkloadKind::Deployment,
+                name: "app".to_string(),
+            })
+        );
+        assert_eq!(
+            WorkloadRef::from_str("web-0").map(|workload| workload.kind),
+            Ok(WorkloadKind::Pod)
+        );
+        assert!(WorkloadRef::from_str("service/app").is_err());
+    }
+}